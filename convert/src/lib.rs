@@ -0,0 +1,166 @@
+//! # Conversion of RGB colours to the eight colours of the display
+//!
+//! Shared by `microbit-two`, which dithers while drawing, and by the
+//! `include_palette8_image!` macro and the `image_convert` example, which
+//! convert PNG images on the host. Colours are indices from 0 to 7 with one
+//! bit per channel, red in the most significant bit, as `Palette8`.
+//!
+//! With the `png` feature, [`Picture`] decodes and quantizes PNG images.
+
+#![no_std]
+
+#[cfg(feature = "png")]
+extern crate std;
+
+#[cfg(feature = "png")]
+mod picture;
+
+#[cfg(feature = "png")]
+pub use picture::Picture;
+
+/// Thresholds of ordered dithering, from 0 to 15
+pub const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Longest run with the length in the run octet, see
+/// `microbit_two::lpm013m126a::image`
+pub const SHORT_RUN: u16 = 31;
+/// Longest run with a length octet
+pub const LONG_RUN: u16 = SHORT_RUN + 1 + 255;
+
+/// Method used to reduce RGB colours to `Palette8`
+#[derive(Clone, Copy, PartialEq)]
+pub enum Dithering {
+    /// Nearest colour, no dithering
+    Nearest,
+    /// Ordered dithering using a 4x4 Bayer matrix
+    Bayer,
+    /// Floyd–Steinberg error diffusion
+    FloydSteinberg,
+}
+
+/// Colour index of thresholded channels
+pub fn index(red: bool, green: bool, blue: bool) -> u8 {
+    (red as u8) << 2 | (green as u8) << 1 | blue as u8
+}
+
+/// Nearest colour, each channel is thresholded at half intensity
+pub fn nearest(rgb: [u8; 3]) -> u8 {
+    index(rgb[0] >= 0x80, rgb[1] >= 0x80, rgb[2] >= 0x80)
+}
+
+/// Ordered dither a single pixel, error diffusion is treated as `Bayer`
+pub fn ordered(method: Dithering, x: i32, y: i32, rgb: [u8; 3]) -> u8 {
+    if method == Dithering::Nearest {
+        return nearest(rgb);
+    }
+    let threshold = BAYER_4X4[(y & 3) as usize][(x & 3) as usize] as u16 * 16 + 8;
+    index(
+        rgb[0] as u16 > threshold,
+        rgb[1] as u16 > threshold,
+        rgb[2] as u16 > threshold,
+    )
+}
+
+/// Floyd–Steinberg error diffusion of pixels in line order
+///
+/// The storage holds the accumulated error per channel of a line, with one
+/// guard entry on each side. Lines are at most two entries shorter than the
+/// storage.
+pub struct ErrorDiffusion<B> {
+    current: B,
+    next: B,
+    x: usize,
+}
+
+impl<B> ErrorDiffusion<B>
+where
+    B: AsMut<[[i16; 3]]>,
+{
+    /// Diffusion using zeroed storage for the current and the next line
+    pub fn new(current: B, next: B) -> Self {
+        Self {
+            current,
+            next,
+            x: 0,
+        }
+    }
+
+    /// Colour index of the next pixel of the line
+    pub fn push(&mut self, rgb: [u8; 3]) -> u8 {
+        let x = self.x;
+        let current = self.current.as_mut();
+        let next = self.next.as_mut();
+        let mut on = [false; 3];
+        for channel in 0..3 {
+            let wanted = rgb[channel] as i16 + current[x + 1][channel];
+            on[channel] = wanted >= 0x80;
+            let error = wanted - if on[channel] { 0xff } else { 0 };
+            current[x + 2][channel] += error * 7 / 16;
+            next[x][channel] += error * 3 / 16;
+            next[x + 1][channel] += error * 5 / 16;
+            next[x + 2][channel] += error / 16;
+        }
+        self.x += 1;
+        index(on[0], on[1], on[2])
+    }
+
+    /// Continue with the first pixel of the next line
+    pub fn next_line(&mut self) {
+        core::mem::swap(&mut self.current, &mut self.next);
+        for error in self.next.as_mut().iter_mut() {
+            *error = [0; 3];
+        }
+        self.x = 0;
+    }
+}
+
+/// Run length encoder producing runs of colour indices while pixels are
+/// added
+#[derive(Default)]
+pub struct Encoder {
+    run: Option<(u8, u16)>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next pixel, completed runs are passed to `write`
+    pub fn push<F>(&mut self, index: u8, write: F)
+    where
+        F: FnMut(&[u8]),
+    {
+        self.run = match self.run {
+            Some((current, length)) if current == index && length < LONG_RUN => {
+                Some((current, length + 1))
+            }
+            Some((current, length)) => {
+                write_run(current, length, write);
+                Some((index, 1))
+            }
+            None => Some((index, 1)),
+        };
+    }
+
+    /// Pass the last run to `write`
+    pub fn finish<F>(&mut self, write: F)
+    where
+        F: FnMut(&[u8]),
+    {
+        if let Some((current, length)) = self.run.take() {
+            write_run(current, length, write);
+        }
+    }
+}
+
+fn write_run<F>(index: u8, length: u16, mut write: F)
+where
+    F: FnMut(&[u8]),
+{
+    if length <= SHORT_RUN {
+        write(&[index << 5 | (length - 1) as u8]);
+    } else {
+        write(&[index << 5 | SHORT_RUN as u8, (length - SHORT_RUN - 1) as u8]);
+    }
+}
@@ -0,0 +1,72 @@
+//! # PNG images
+//!
+//! Transparent pixels are blended with black.
+
+use crate::{nearest, ordered, Dithering, ErrorDiffusion};
+use std::{io::Read, vec, vec::Vec};
+
+/// Decoded image with RGB pixels in row order
+pub struct Picture {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+/// Blend a colour channel with black
+fn blend(value: u8, alpha: u8) -> u8 {
+    (value as u16 * alpha as u16 / 255) as u8
+}
+
+impl Picture {
+    /// Decode a PNG image
+    pub fn read<R: Read>(reader: R) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info()?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data)?;
+        let channels = info.color_type.samples();
+        let pixels = data[..info.buffer_size()]
+            .chunks(channels)
+            .map(|pixel| match info.color_type {
+                png::ColorType::Grayscale => [pixel[0]; 3],
+                png::ColorType::GrayscaleAlpha => [blend(pixel[0], pixel[1]); 3],
+                png::ColorType::Rgba => [
+                    blend(pixel[0], pixel[3]),
+                    blend(pixel[1], pixel[3]),
+                    blend(pixel[2], pixel[3]),
+                ],
+                _ => [pixel[0], pixel[1], pixel[2]],
+            })
+            .collect();
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    /// Colour indices of the pixels, the Bayer pattern starts at the top
+    /// left corner of the image
+    pub fn quantize(&self, method: Dithering) -> Vec<u8> {
+        let width = self.width as usize;
+        let mut diffusion =
+            ErrorDiffusion::new(vec![[0i16; 3]; width + 2], vec![[0i16; 3]; width + 2]);
+        let mut indices = Vec::with_capacity(self.pixels.len());
+        for (i, &pixel) in self.pixels.iter().enumerate() {
+            let (x, y) = (i % width, i / width);
+            indices.push(match method {
+                Dithering::Nearest => nearest(pixel),
+                Dithering::Bayer => ordered(method, x as i32, y as i32, pixel),
+                Dithering::FloydSteinberg => {
+                    let index = diffusion.push(pixel);
+                    if x + 1 == width {
+                        diffusion.next_line();
+                    }
+                    index
+                }
+            });
+        }
+        indices
+    }
+}
@@ -0,0 +1,59 @@
+//! Converts a PNG image to a run length encoded `Palette8` image
+//!
+//! ```
+//! $ cargo run --example image_convert --no-default-features --features simulator \
+//!     --target x86_64-unknown-linux-gnu -- splash.png splash.rle [nearest|bayer|floyd-steinberg]
+//! ```
+//!
+//! The colours are reduced to the eight colours of the display, by default
+//! with Floyd–Steinberg dithering. The conversion is the same as of the
+//! `include_palette8_image!` macro. The result can be included in the
+//! firmware using `include_bytes!` and drawn with `RleImage`.
+
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    process,
+};
+
+use microbit_two::lpm013m126a::{image, Dithering, Palette8};
+use microbit_two_convert::Picture;
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (input, output) = match args.as_slice() {
+        [input, output, ..] => (input, output),
+        _ => {
+            eprintln!(
+                "Usage: image_convert <input.png> <output.rle> [nearest|bayer|floyd-steinberg]"
+            );
+            process::exit(2);
+        }
+    };
+    let method = match args.get(2).map(String::as_str) {
+        None | Some("floyd-steinberg") => Dithering::FloydSteinberg,
+        Some("bayer") => Dithering::Bayer,
+        Some("nearest") => Dithering::Nearest,
+        Some(method) => {
+            eprintln!("Unknown dithering method {}", method);
+            process::exit(2);
+        }
+    };
+
+    let picture = Picture::read(BufReader::new(File::open(input)?))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let (width, height) = (picture.width, picture.height);
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        eprintln!("The image is too large");
+        process::exit(1);
+    }
+    let indices = picture.quantize(method);
+    let encoded = image::encode(
+        width as u16,
+        height as u16,
+        indices.into_iter().map(Palette8::from),
+    );
+    std::fs::write(output, &encoded)?;
+    println!("{}x{} pixels, {} octets", width, height, encoded.len());
+    Ok(())
+}
@@ -0,0 +1,182 @@
+//! Renders a QR code on the host and compares it with a reference encoder
+//!
+//! ```
+//! $ cargo run --example qr_code --no-default-features --features simulator \
+//!     --target x86_64-unknown-linux-gnu -- "https://microbit.org" qr.png [low|medium|quartile|high]
+//! ```
+//!
+//! The modules are read back from the frame buffer and compared with the
+//! code of `qrcodegen`, using the same mask. With `--check` codes of every
+//! supported length are checked for each error correction level, and the
+//! example fails if any code differs.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    process,
+};
+
+use embedded_graphics::{geometry::Dimensions, Drawable};
+use microbit_two::lpm013m126a::{
+    panel,
+    qr::{self, ErrorCorrection, Mode},
+    simulator, FrameBuffer, Palette8, QrCode, QrView,
+};
+use qrcodegen::{Mask, QrCodeEcc, QrSegment, Version};
+
+const LEVELS: [ErrorCorrection; 4] = [
+    ErrorCorrection::Low,
+    ErrorCorrection::Medium,
+    ErrorCorrection::Quartile,
+    ErrorCorrection::High,
+];
+
+/// Draw the code fitted to the display and compare the modules read from
+/// the frame buffer with the reference code of the data
+fn round_trip(code: &QrCode, data: &[u8], mode: Mode) -> (FrameBuffer<panel::Lpm013m126a>, bool) {
+    let mut frame_buffer = FrameBuffer::<panel::Lpm013m126a>::new();
+    frame_buffer.fill(Palette8::Blue);
+    let view = QrView::fit(code, frame_buffer.bounding_box());
+    let _ = view.draw(&mut frame_buffer);
+
+    let segment = match mode {
+        Mode::Alphanumeric => match std::str::from_utf8(data) {
+            Ok(text) => QrSegment::make_alphanumeric(text),
+            Err(_) => return (frame_buffer, false),
+        },
+        Mode::Byte => QrSegment::make_bytes(data),
+    };
+    let reference = match qrcodegen::QrCode::encode_segments_advanced(
+        &[segment],
+        reference_level(code.error_correction()),
+        Version::MIN,
+        Version::MAX,
+        Some(Mask::new(code.mask())),
+        false,
+    ) {
+        Ok(reference) => reference,
+        Err(_) => return (frame_buffer, false),
+    };
+    if reference.size() != code.size() as i32 {
+        return (frame_buffer, false);
+    }
+
+    // Sample the centre of each module
+    let scale = view.size().width as i32 / (code.size() as i32 + 2 * qr::QUIET_ZONE as i32);
+    let origin = view.bounding_box().top_left;
+    let offset = qr::QUIET_ZONE as i32 * scale + scale / 2;
+    let size = code.size() as i32;
+    let same = (0..size).all(|y| {
+        (0..size).all(|x| {
+            let (px, py) = (origin.x + offset + x * scale, origin.y + offset + y * scale);
+            let dark = frame_buffer.get_pixel(px as u16, py as u16) == Palette8::Black;
+            dark == reference.get_module(x, y)
+        })
+    });
+    (frame_buffer, same)
+}
+
+fn reference_level(ecc: ErrorCorrection) -> QrCodeEcc {
+    match ecc {
+        ErrorCorrection::Low => QrCodeEcc::Low,
+        ErrorCorrection::Medium => QrCodeEcc::Medium,
+        ErrorCorrection::Quartile => QrCodeEcc::Quartile,
+        ErrorCorrection::High => QrCodeEcc::High,
+    }
+}
+
+/// Check data of every length in both modes
+fn check() -> usize {
+    let mut failures = 0;
+    for &ecc in LEVELS.iter() {
+        for &mode in [Mode::Alphanumeric, Mode::Byte].iter() {
+            let mut length = 1;
+            loop {
+                let data: Vec<u8> = match mode {
+                    Mode::Alphanumeric => b"MICROBIT-V2 $%*+./:0123456789"
+                        .iter()
+                        .cycle()
+                        .take(length)
+                        .copied()
+                        .collect(),
+                    Mode::Byte => (0..length).map(|i| (i * 7 + length) as u8).collect(),
+                };
+                let code = match QrCode::encode(&data, mode, ecc) {
+                    Ok(code) => code,
+                    Err(_) => break,
+                };
+                let (_, same) = round_trip(&code, &data, mode);
+                if !same {
+                    eprintln!(
+                        "Failed, length {} version {} mask {}",
+                        length,
+                        code.version(),
+                        code.mask()
+                    );
+                    failures += 1;
+                }
+                // Every length for small versions, then in larger steps
+                length += if code.version() < 10 { 1 } else { 37 };
+            }
+            println!("Checked up to {} characters", length - 1);
+        }
+    }
+    failures
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("--check") {
+        let failures = check();
+        if failures > 0 {
+            eprintln!("{} codes differ from the reference", failures);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+    let (text, path) = match args.as_slice() {
+        [text, path, ..] => (text, path),
+        _ => {
+            eprintln!("Usage: qr_code <text> <output.png> [low|medium|quartile|high]");
+            eprintln!("       qr_code --check");
+            process::exit(2);
+        }
+    };
+    let ecc = match args.get(2).map(String::as_str) {
+        None | Some("medium") => ErrorCorrection::Medium,
+        Some("low") => ErrorCorrection::Low,
+        Some("quartile") => ErrorCorrection::Quartile,
+        Some("high") => ErrorCorrection::High,
+        Some(level) => {
+            eprintln!("Unknown error correction level {}", level);
+            process::exit(2);
+        }
+    };
+    let code = match QrCode::encode_text(text, ecc) {
+        Ok(code) => code,
+        Err(_) => {
+            eprintln!("The text does not fit in a QR code on the display");
+            process::exit(1);
+        }
+    };
+    let mode = if QrSegment::is_alphanumeric(text) {
+        Mode::Alphanumeric
+    } else {
+        Mode::Byte
+    };
+    let (frame_buffer, same) = round_trip(&code, text.as_bytes(), mode);
+    let file = BufWriter::new(File::create(path)?);
+    simulator::write_png(&frame_buffer, file)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    println!(
+        "Version {}, {} modules, mask {}",
+        code.version(),
+        code.size(),
+        code.mask()
+    );
+    if !same {
+        eprintln!("The drawn code differs from the reference");
+        process::exit(1);
+    }
+    Ok(())
+}
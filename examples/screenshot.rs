@@ -0,0 +1,139 @@
+//! Converts a screenshot logged by the device to a PNG image
+//!
+//! ```
+//! $ cargo run --release --bin display_widgets | tee capture.log
+//! $ cargo run --example screenshot --no-default-features --features simulator \
+//!     --target x86_64-unknown-linux-gnu -- capture.log screen.png
+//! ```
+//!
+//! The log is read from standard input when the path is `-`. With several
+//! screenshots in the log, the last complete one is converted.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter},
+    process,
+};
+
+use microbit_two::lpm013m126a::RleImage;
+
+/// Collect the chunks of the screenshots in a log
+#[derive(Default)]
+struct Capture {
+    data: Vec<u8>,
+    chunks: u16,
+    broken: Option<String>,
+    complete: Option<Vec<u8>>,
+}
+
+impl Capture {
+    fn line(&mut self, line: &str) {
+        let rest = match line.find("screenshot ") {
+            Some(i) => &line[i + "screenshot ".len()..],
+            None => return,
+        };
+        let mut fields = rest.split_whitespace();
+        match fields.next() {
+            Some("begin") => {
+                *self = Self {
+                    complete: self.complete.take(),
+                    ..Self::default()
+                }
+            }
+            Some("end") => {
+                let chunks = fields.next().and_then(|f| f.parse::<u16>().ok());
+                let octets = fields.next().and_then(|f| f.parse::<usize>().ok());
+                if self.broken.is_none()
+                    && (chunks != Some(self.chunks) || octets != Some(self.data.len()))
+                {
+                    self.broken = Some(format!(
+                        "expected {:?} chunks of {:?} octets, got {} of {}",
+                        chunks,
+                        octets,
+                        self.chunks,
+                        self.data.len()
+                    ));
+                }
+                match self.broken.take() {
+                    Some(reason) => eprintln!("Incomplete screenshot, {}", reason),
+                    None => self.complete = Some(std::mem::take(&mut self.data)),
+                }
+            }
+            Some(sequence) => {
+                let expected = self.chunks;
+                if sequence.parse::<u16>().ok() != Some(expected) {
+                    self.broken
+                        .get_or_insert_with(|| format!("chunk {} missing", expected));
+                }
+                self.chunks = self.chunks.wrapping_add(1);
+                if let Some(octets) = parse_octets(rest) {
+                    self.data.extend(octets);
+                } else {
+                    self.broken
+                        .get_or_insert_with(|| format!("chunk {} unreadable", sequence));
+                }
+            }
+            None => (),
+        }
+    }
+}
+
+/// The octets of a `[1, 2, 3]` list
+fn parse_octets(text: &str) -> Option<Vec<u8>> {
+    let start = text.find('[')?;
+    let end = start + text[start..].find(']')?;
+    text[start + 1..end]
+        .split(',')
+        .map(|octet| octet.trim().parse::<u8>().ok())
+        .collect()
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (input, output) = match args.as_slice() {
+        [input, output] => (input, output),
+        _ => {
+            eprintln!("Usage: screenshot <capture.log|-> <output.png>");
+            process::exit(2);
+        }
+    };
+    let reader: Box<dyn BufRead> = if input == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(File::open(input)?))
+    };
+    let mut capture = Capture::default();
+    for line in reader.lines() {
+        capture.line(&line?);
+    }
+    let data = match capture.complete {
+        Some(data) => data,
+        None => {
+            eprintln!("No complete screenshot in the log");
+            process::exit(1);
+        }
+    };
+    let image = match RleImage::new(&data) {
+        Ok(image) => image,
+        Err(_) => {
+            eprintln!("The screenshot is not a valid image");
+            process::exit(1);
+        }
+    };
+
+    let mut rgb = Vec::with_capacity(image.width() as usize * image.height() as usize * 3);
+    for colour in image.pixels() {
+        let (r, g, b) = colour.rgb();
+        rgb.extend_from_slice(&[r as u8 * 0xff, g as u8 * 0xff, b as u8 * 0xff]);
+    }
+    let file = BufWriter::new(File::create(output)?);
+    let mut encoder = png::Encoder::new(file, image.width() as u32, image.height() as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&rgb))
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    println!("{}x{} screenshot", image.width(), image.height());
+    Ok(())
+}
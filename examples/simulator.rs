@@ -0,0 +1,77 @@
+//! Renders the `display_draw` screen on the host
+//!
+//! ```
+//! $ cargo run --example simulator --no-default-features --features simulator \
+//!     --target x86_64-unknown-linux-gnu -- display.png
+//! ```
+//!
+//! The image is written as PNG or PPM depending on the file extension. With
+//! `--golden <image.ppm>` the rendered screen is compared against the golden
+//! image instead, and the example fails if any pixel differs.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    process,
+};
+
+use embedded_graphics::{
+    geometry::{Point, Size},
+    primitives::{Circle, Primitive, PrimitiveStyleBuilder, Rectangle},
+    Drawable,
+};
+use microbit_two::lpm013m126a::{
+    panel, simulator, Alignment, Font, FrameBuffer, Palette8, TextBox, TextStyle,
+};
+
+fn main() -> io::Result<()> {
+    let mut frame_buffer = FrameBuffer::<panel::Lpm013m126a>::new();
+    frame_buffer.fill(Palette8::Green);
+
+    let background_style = PrimitiveStyleBuilder::new()
+        .stroke_width(5)
+        .stroke_color(Palette8::Black)
+        .fill_color(Palette8::Black)
+        .build();
+    let _ = Rectangle::new(Point::new(0, 0), Size::new(176, 176))
+        .into_styled(background_style)
+        .draw(&mut frame_buffer);
+
+    let circle_style = PrimitiveStyleBuilder::new()
+        .stroke_width(3)
+        .stroke_color(Palette8::Cyan)
+        .build();
+    let _ = Circle::new(Point::new(22, 22), 20)
+        .into_styled(circle_style)
+        .draw(&mut frame_buffer);
+
+    let _ = TextBox::new(
+        "micro:bit v2",
+        Rectangle::new(Point::new(0, 156), Size::new(176, 20)),
+        TextStyle::new(Font::Medium, Palette8::White).with_alignment(Alignment::Centre),
+    )
+    .draw(&mut frame_buffer);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [flag, golden] if flag == "--golden" => {
+            let golden = BufReader::new(File::open(golden)?);
+            let differing = simulator::compare_ppm(&frame_buffer, golden)?;
+            if differing > 0 {
+                eprintln!("{} pixels differ from the golden image", differing);
+                process::exit(1);
+            }
+        }
+        _ => {
+            let path = args.first().map(String::as_str).unwrap_or("display.png");
+            let file = BufWriter::new(File::create(path)?);
+            if path.ends_with(".ppm") {
+                simulator::write_ppm(&frame_buffer, file)?;
+            } else {
+                simulator::write_png(&frame_buffer, file)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+//! # Build time image conversion
+//!
+//! Procedural macros loading PNG images when compiling, so that image assets
+//! do not have to be written as arrays by hand. Paths are relative to the
+//! directory of the `Cargo.toml` of the crate using the macro. The image is
+//! included with `include_bytes!` as well, so that changing it rebuilds the
+//! crate.
+//!
+//! The conversion is shared with the `image_convert` example, see
+//! `microbit-two-convert`. Transparent pixels are blended with black.
+//!
+//! The macros are re-exported by `microbit-two` with the `macros` feature.
+
+use microbit_two_convert::{Dithering, Encoder, Picture};
+use proc_macro::TokenStream;
+use proc_macro2::Literal;
+use quote::quote;
+use std::{fs::File, io::BufReader, path::PathBuf};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, Ident, LitStr, Token,
+};
+
+const DISPLAY_WIDTH: u32 = 176;
+const DISPLAY_HEIGHT: u32 = 176;
+const MATRIX_SIZE: u32 = 5;
+
+/// Image path and dithering method
+struct Arguments {
+    path: LitStr,
+    dithering: Dithering,
+}
+
+impl Parse for Arguments {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let mut dithering = Dithering::Nearest;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let method: Ident = input.parse()?;
+            dithering = match method.to_string().as_str() {
+                "Nearest" => Dithering::Nearest,
+                "Bayer" => Dithering::Bayer,
+                "FloydSteinberg" => Dithering::FloydSteinberg,
+                _ => {
+                    return Err(syn::Error::new(
+                        method.span(),
+                        "expected `Nearest`, `Bayer` or `FloydSteinberg`",
+                    ))
+                }
+            };
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(Self { path, dithering })
+    }
+}
+
+/// Load the image at a path relative to the crate directory
+fn load(path: &LitStr) -> syn::Result<(PathBuf, Picture)> {
+    let error = |message: String| syn::Error::new(path.span(), message);
+    let directory = std::env::var("CARGO_MANIFEST_DIR").map_err(|e| error(e.to_string()))?;
+    let full_path = PathBuf::from(directory).join(path.value());
+    let file = File::open(&full_path)
+        .map_err(|e| error(format!("failed to open {}: {}", full_path.display(), e)))?;
+    let picture = Picture::read(BufReader::new(file))
+        .map_err(|e| error(format!("invalid image {}: {}", path.value(), e)))?;
+    Ok((full_path, picture))
+}
+
+/// Run length encode colour indices
+fn encode(indices: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut encoder = Encoder::new();
+    for &index in indices {
+        encoder.push(index, |octets| data.extend_from_slice(octets));
+    }
+    encoder.finish(|octets| data.extend_from_slice(octets));
+    data
+}
+
+/// Include a PNG image as a run length encoded `Palette8` image
+///
+/// Expands to a `microbit_two::lpm013m126a::RleImage` constant expression.
+/// The image must not be larger than the display. The colours are reduced to
+/// the eight colours of the display using the optional dithering method,
+/// `Nearest`, `Bayer` or `FloydSteinberg`. The default is `Nearest`. The
+/// Bayer pattern starts at the top left corner of the image.
+///
+/// ```ignore
+/// const LOGO: RleImage = include_palette8_image!("assets/logo.png", FloydSteinberg);
+///
+/// Image::new(&LOGO, Point::new(8, 8)).draw(&mut display)?;
+/// ```
+#[proc_macro]
+pub fn include_palette8_image(input: TokenStream) -> TokenStream {
+    let arguments = parse_macro_input!(input as Arguments);
+    let (path, picture) = match load(&arguments.path) {
+        Ok(loaded) => loaded,
+        Err(error) => return error.to_compile_error().into(),
+    };
+    if picture.width > DISPLAY_WIDTH || picture.height > DISPLAY_HEIGHT {
+        return syn::Error::new(
+            arguments.path.span(),
+            format!(
+                "{} is {}x{} pixels, larger than the {}x{} pixels of the display",
+                arguments.path.value(),
+                picture.width,
+                picture.height,
+                DISPLAY_WIDTH,
+                DISPLAY_HEIGHT
+            ),
+        )
+        .to_compile_error()
+        .into();
+    }
+    let runs = Literal::byte_string(&encode(&picture.quantize(arguments.dithering)));
+    let width = picture.width as u16;
+    let height = picture.height as u16;
+    let path = path.to_string_lossy();
+    quote!({
+        const _: &[u8] = include_bytes!(#path);
+        ::microbit_two::lpm013m126a::RleImage::from_raw_parts(#width, #height, #runs)
+    })
+    .into()
+}
+
+/// Include a 5x5 PNG image as a LED matrix image
+///
+/// Expands to a `microbit_two::Image` constant expression, with the
+/// brightness of each LED taken from the luma of the pixel.
+///
+/// ```ignore
+/// const GLOW: Image = include_matrix_image!("assets/glow.png");
+///
+/// led_matrix.display(GLOW);
+/// ```
+#[proc_macro]
+pub fn include_matrix_image(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    let (full_path, picture) = match load(&path) {
+        Ok(loaded) => loaded,
+        Err(error) => return error.to_compile_error().into(),
+    };
+    if picture.width != MATRIX_SIZE || picture.height != MATRIX_SIZE {
+        return syn::Error::new(
+            path.span(),
+            format!(
+                "{} is {}x{} pixels, the LED matrix is {}x{} pixels",
+                path.value(),
+                picture.width,
+                picture.height,
+                MATRIX_SIZE,
+                MATRIX_SIZE
+            ),
+        )
+        .to_compile_error()
+        .into();
+    }
+    let rows = picture.pixels.chunks(MATRIX_SIZE as usize).map(|row| {
+        let levels = row.iter().map(|pixel| {
+            let luma =
+                (pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000;
+            luma as u8
+        });
+        quote!([#(#levels),*])
+    });
+    let path = full_path.to_string_lossy();
+    quote!({
+        const _: &[u8] = include_bytes!(#path);
+        [#(#rows),*]
+    })
+    .into()
+}
@@ -15,7 +15,10 @@ mod app {
         gpio::{self, Output, PushPull},
         timer::Instance,
     };
-    use microbit_two::{lpm013m126a, spim};
+    use microbit_two::{
+        lpm013m126a::{self, LINE_BUFFER_OCTETS},
+        spim,
+    };
     use pac::{RTC0, TIMER0, TIMER1, TIMER2};
 
     #[local]
@@ -35,7 +38,7 @@ mod app {
         jdi: lpm013m126a::Lpm013m126a<pac::SPIM3, hal::gpio::p0::P0_03<Output<PushPull>>>,
     }
 
-    #[init]
+    #[init(local = [line_buffer: [u8; LINE_BUFFER_OCTETS] = [0; LINE_BUFFER_OCTETS]])]
     fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
         // Configure to use external clocks, and start them
         let _clocks = clocks::Clocks::new(cx.device.CLOCK)
@@ -110,6 +113,7 @@ mod app {
         let mut jdi = lpm013m126a::Lpm013m126a::new(
             jdi_spi,
             port0.p0_03.into_push_pull_output(gpio::Level::Low),
+            cx.local.line_buffer,
         );
 
         let jdi_com = port1
@@ -122,8 +126,8 @@ mod app {
             Ok(_) => (),
         }
 
-        for y in 0..lpm013m126a::DISPLAY_HEIGHT as u8 {
-            for x in 0..lpm013m126a::DISPLAY_WIDTH as u8 {
+        for y in 0..u16::from(lpm013m126a::DISPLAY_HEIGHT) {
+            for x in 0..u16::from(lpm013m126a::DISPLAY_WIDTH) {
                 jdi.set_pixel(x, y, lpm013m126a::Palette8::White);
             }
         }
@@ -171,8 +175,8 @@ mod app {
             *cx.local.colour = 0;
         }
         let c = microbit_two::lpm013m126a::Palette8::from(*cx.local.colour);
-        let y = (lpm013m126a::lpm013m126a::DISPLAY_HEIGHT / 2) as u8;
-        for x in 0..lpm013m126a::lpm013m126a::DISPLAY_WIDTH as u8 {
+        let y = u16::from(lpm013m126a::DISPLAY_HEIGHT / 2);
+        for x in 0..u16::from(lpm013m126a::DISPLAY_WIDTH) {
             cx.shared.jdi.set_pixel(x, y, c);
         }
         let _ = cx.shared.jdi.update_display();
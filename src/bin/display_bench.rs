@@ -0,0 +1,176 @@
+#![no_main]
+#![no_std]
+
+use microbit_two::hal;
+use microbit_two::hal::pac;
+use rtic::app;
+
+#[app(device = pac, peripherals = true)]
+mod app {
+    use super::{hal, pac};
+
+    use cortex_m::peripheral::DWT;
+    use embedded_graphics::{
+        draw_target::DrawTarget,
+        geometry::{Dimensions, Point, Size},
+        primitives::Rectangle,
+        Pixel,
+    };
+    use embedded_hal::digital::v2::{OutputPin, StatefulOutputPin};
+    use hal::{
+        clocks,
+        gpio::{self, Output, PushPull},
+        timer::Instance,
+    };
+    use microbit_two::{
+        lpm013m126a::{self, Palette8, DISPLAY_HEIGHT, DISPLAY_WIDTH, LINE_BUFFER_OCTETS},
+        spim,
+    };
+    use pac::TIMER1;
+
+    #[local]
+    struct Local {
+        timer_1: TIMER1,
+        jdi_com: hal::gpio::Pin<hal::gpio::Output<hal::gpio::PushPull>>,
+    }
+
+    #[shared]
+    struct Shared {
+        #[lock_free]
+        jdi: lpm013m126a::Lpm013m126a<pac::SPIM3, hal::gpio::p0::P0_03<Output<PushPull>>>,
+    }
+
+    /// Run `f` and return the number of CPU cycles it took
+    fn cycles<F: FnOnce()>(f: F) -> u32 {
+        let start = DWT::cycle_count();
+        f();
+        DWT::cycle_count().wrapping_sub(start)
+    }
+
+    #[init(local = [line_buffer: [u8; LINE_BUFFER_OCTETS] = [0; LINE_BUFFER_OCTETS]])]
+    fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let mut core = cx.core;
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // Configure to use external clocks, and start them
+        let _clocks = clocks::Clocks::new(cx.device.CLOCK)
+            .enable_ext_hfosc()
+            .set_lfclk_src_synth()
+            .start_lfclk();
+
+        let port0 = gpio::p0::Parts::new(cx.device.P0);
+        let port1 = gpio::p1::Parts::new(cx.device.P1);
+
+        cx.device.TIMER1.set_periodic();
+        cx.device.TIMER1.enable_interrupt();
+        cx.device.TIMER1.timer_start(1_000_000_u32);
+
+        let jdi_spi = spim::Spim::new(
+            cx.device.SPIM3,
+            spim::Pins {
+                sck: port0
+                    .p0_17
+                    .into_push_pull_output(gpio::Level::High)
+                    .degrade(),
+                mosi: Some(
+                    port0
+                        .p0_13
+                        .into_push_pull_output(gpio::Level::High)
+                        .degrade(),
+                ),
+                miso: None,
+                csn: Some(
+                    port0
+                        .p0_02
+                        .into_push_pull_output(gpio::Level::Low)
+                        .degrade(),
+                ),
+                csn_pol: true,
+            },
+            hal::spim::Frequency::M4,
+            hal::spim::MODE_0,
+            0,
+        );
+        let mut jdi = lpm013m126a::Lpm013m126a::new(
+            jdi_spi,
+            port0.p0_03.into_push_pull_output(gpio::Level::Low),
+            cx.local.line_buffer,
+        );
+
+        let jdi_com = port1
+            .p1_02
+            .into_push_pull_output(gpio::Level::Low)
+            .degrade();
+
+        match jdi.init() {
+            Err(_) => defmt::error!("Failed to initialize JDI"),
+            Ok(_) => (),
+        }
+
+        let screen = jdi.bounding_box();
+        let odd_area = Rectangle::new(Point::new(13, 7), Size::new(151, 160));
+
+        let n = cycles(|| {
+            for y in 0..u16::from(DISPLAY_HEIGHT) {
+                for x in 0..u16::from(DISPLAY_WIDTH) {
+                    jdi.set_pixel(x, y, Palette8::Red);
+                }
+            }
+        });
+        defmt::info!("set_pixel, full screen: {=u32} cycles", n);
+
+        let n = cycles(|| {
+            let _ = jdi.draw_iter(screen.points().map(|p| Pixel(p, Palette8::Green)));
+        });
+        defmt::info!("draw_iter, full screen: {=u32} cycles", n);
+
+        let n = cycles(|| {
+            let _ = jdi.fill_contiguous(&screen, core::iter::repeat(Palette8::Blue));
+        });
+        defmt::info!("fill_contiguous, full screen: {=u32} cycles", n);
+
+        let n = cycles(|| {
+            let _ = jdi.fill_solid(&screen, Palette8::Yellow);
+        });
+        defmt::info!("fill_solid, full screen: {=u32} cycles", n);
+
+        let n = cycles(|| {
+            let _ = jdi.fill_solid(&odd_area, Palette8::Cyan);
+        });
+        defmt::info!("fill_solid, 151x160 at odd x: {=u32} cycles", n);
+
+        let n = cycles(|| {
+            let _ = jdi.clear(Palette8::White);
+        });
+        defmt::info!("clear: {=u32} cycles", n);
+
+        let _ = jdi.update_display();
+
+        let shared = Shared { jdi };
+        let local = Local {
+            timer_1: cx.device.TIMER1,
+            jdi_com,
+        };
+        (shared, local, init::Monotonics())
+    }
+
+    #[task(binds = TIMER1, local = [timer_1, jdi_com])]
+    fn timer1(cx: timer1::Context) {
+        cx.local.timer_1.timer_reset_event();
+        let high = match cx.local.jdi_com.is_set_high() {
+            Ok(s) => s,
+            Err(_) => false,
+        };
+        if high {
+            let _ = cx.local.jdi_com.set_low();
+        } else {
+            let _ = cx.local.jdi_com.set_high();
+        }
+    }
+
+    #[task(binds = SPIM3, shared = [jdi])]
+    fn display_spi(cx: display_spi::Context) {
+        cx.shared.jdi.spi_task_event();
+    }
+}
@@ -0,0 +1,201 @@
+#![no_main]
+#![no_std]
+
+use microbit_two::hal;
+use microbit_two::hal::pac;
+use rtic::app;
+
+#[app(device = pac, peripherals = true)]
+mod app {
+    use super::{hal, pac};
+
+    use embedded_graphics::{
+        geometry::{Point, Size},
+        primitives::Rectangle,
+    };
+    use embedded_hal::digital::v2::InputPin;
+    use hal::{
+        clocks,
+        gpio::{self, Floating, Input, Output, PushPull},
+        gpiote::Gpiote,
+        ppi::{self, Ppi19},
+    };
+    use microbit_two::{
+        lpm013m126a::{self, AnalogClock, DigitalClock, Theme, Widget, LINE_BUFFER_OCTETS},
+        spim, Clock, DateTime, Vcom,
+    };
+    use pac::{RTC0, RTC1};
+
+    type Jdi = lpm013m126a::Lpm013m126a<pac::SPIM3, hal::gpio::p0::P0_03<Output<PushPull>>>;
+
+    pub struct Faces {
+        pub analog: AnalogClock,
+        pub digital: DigitalClock,
+        pub digital_shown: bool,
+    }
+
+    impl Faces {
+        pub fn shown(&mut self) -> &mut dyn Widget<Jdi> {
+            if self.digital_shown {
+                &mut self.digital
+            } else {
+                &mut self.analog
+            }
+        }
+    }
+
+    pub struct Buttons {
+        pub a: hal::gpio::p0::P0_14<Input<Floating>>,
+        pub b: hal::gpio::p0::P0_23<Input<Floating>>,
+    }
+
+    #[local]
+    struct Local {
+        clock: Clock<RTC0>,
+        _vcom: Vcom<RTC1, Ppi19>,
+        faces: Faces,
+        buttons: Buttons,
+    }
+
+    #[shared]
+    struct Shared {
+        #[lock_free]
+        jdi: Jdi,
+    }
+
+    #[init(local = [line_buffer: [u8; LINE_BUFFER_OCTETS] = [0; LINE_BUFFER_OCTETS]])]
+    fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
+        // Only the RTCs run between updates, use the low power RC oscillator
+        let _clocks = clocks::Clocks::new(cx.device.CLOCK)
+            .set_lfclk_src_rc()
+            .start_lfclk();
+
+        let port0 = gpio::p0::Parts::new(cx.device.P0);
+        let port1 = gpio::p1::Parts::new(cx.device.P1);
+
+        let jdi_spi = spim::Spim::new(
+            cx.device.SPIM3,
+            spim::Pins {
+                sck: port0
+                    .p0_17
+                    .into_push_pull_output(gpio::Level::High)
+                    .degrade(),
+                mosi: Some(
+                    port0
+                        .p0_13
+                        .into_push_pull_output(gpio::Level::High)
+                        .degrade(),
+                ),
+                miso: None,
+                csn: Some(
+                    port0
+                        .p0_02
+                        .into_push_pull_output(gpio::Level::Low)
+                        .degrade(),
+                ),
+                csn_pol: true,
+            },
+            hal::spim::Frequency::M4,
+            hal::spim::MODE_0,
+            0,
+        );
+        let mut jdi = lpm013m126a::Lpm013m126a::new(
+            jdi_spi,
+            port0.p0_03.into_push_pull_output(gpio::Level::Low),
+            cx.local.line_buffer,
+        );
+
+        let jdi_com = port1
+            .p1_02
+            .into_push_pull_output(gpio::Level::Low)
+            .degrade();
+        let gpiote = Gpiote::new(cx.device.GPIOTE);
+        let ppi = ppi::Parts::new(cx.device.PPI);
+        let vcom = match Vcom::new(cx.device.RTC1, jdi_com, &gpiote.channel7(), ppi.ppi19, 1) {
+            Ok(vcom) => vcom,
+            Err(_) => unreachable!(),
+        };
+
+        match jdi.init() {
+            Err(_) => defmt::error!("Failed to initialize JDI"),
+            Ok(_) => (),
+        }
+        jdi.hold();
+
+        let time = DateTime::new(2022, 1, 1, 12, 0, 0);
+        let clock = match Clock::new(cx.device.RTC0, time) {
+            Ok(clock) => clock,
+            Err(_) => unreachable!(),
+        };
+
+        let area = Rectangle::new(
+            Point::zero(),
+            Size::new(
+                u32::from(lpm013m126a::DISPLAY_WIDTH),
+                u32::from(lpm013m126a::DISPLAY_HEIGHT),
+            ),
+        );
+        let mut faces = Faces {
+            analog: AnalogClock::new(time),
+            digital: DigitalClock::new(time).with_seconds(true),
+            digital_shown: false,
+        };
+        Widget::<Jdi>::set_bounds(&mut faces.analog, area);
+        Widget::<Jdi>::set_bounds(&mut faces.digital, area);
+
+        let buttons = Buttons {
+            a: port0.p0_14.into_floating_input(),
+            b: port0.p0_23.into_floating_input(),
+        };
+
+        defmt::info!("Initialized");
+
+        let shared = Shared { jdi };
+        let local = Local {
+            clock,
+            _vcom: vcom,
+            faces,
+            buttons,
+        };
+        (shared, local, init::Monotonics())
+    }
+
+    #[task(binds = RTC0, local = [clock, faces, buttons], shared = [jdi])]
+    fn rtc(cx: rtc::Context) {
+        let clock = cx.local.clock;
+        if !clock.handle_interrupt() {
+            return;
+        }
+        let faces = cx.local.faces;
+        let jdi = cx.shared.jdi;
+
+        // The buttons are active low and read once a second. Holding A
+        // advances the hours and B the minutes, both change the face.
+        let a = cx.local.buttons.a.is_low().unwrap_or(false);
+        let b = cx.local.buttons.b.is_low().unwrap_or(false);
+        if a && b {
+            faces.digital_shown = !faces.digital_shown;
+            faces.shown().mark_dirty();
+        } else if a || b {
+            let step = if a { 3600 } else { 60 };
+            clock.set(DateTime::from_seconds(clock.seconds() + step));
+        }
+
+        let now = clock.now();
+        faces.analog.set_time(now);
+        faces.digital.set_time(now);
+        let face = faces.shown();
+        if face.is_dirty() {
+            let _ = face.draw(jdi, &Theme::default());
+            // Send the frame, then disable the SPIM until the next change
+            let _ = jdi.update_display();
+            let _ = jdi.resume();
+            jdi.hold();
+        }
+    }
+
+    #[task(binds = SPIM3, shared = [jdi])]
+    fn display_spi(cx: display_spi::Context) {
+        cx.shared.jdi.spi_task_event();
+    }
+}
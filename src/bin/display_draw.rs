@@ -9,6 +9,7 @@ use rtic::app;
 mod app {
     use super::{hal, pac};
 
+    use cortex_m::peripheral::DWT;
     use embedded_graphics::{
         geometry::{Point, Size},
         primitives::{Circle, Primitive, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, Styled},
@@ -21,16 +22,31 @@ mod app {
         timer::Instance,
     };
     use microbit_two::{
-        lpm013m126a::{self, Palette8, DISPLAY_HEIGHT, DISPLAY_WIDTH},
+        lpm013m126a::{
+            self, panel, Alignment, Font, Palette8, Panel, TextBox, TextStyle, DISPLAY_HEIGHT,
+            DISPLAY_WIDTH, LINE_BUFFER_OCTETS,
+        },
         spim,
     };
     use pac::{RTC0, TIMER0, TIMER1, TIMER2};
 
+    // Frame buffer with a line update command per line, sent without copying
+    const FRAME_OCTETS: usize = <panel::Lpm013m126a as Panel>::COMMAND_FRAME_OCTETS;
+
+    // Drawing into a back buffer while the front buffer is sent
+    type Jdi = lpm013m126a::DoubleBuffered<
+        pac::SPIM3,
+        hal::gpio::p0::P0_03<Output<PushPull>>,
+        panel::Lpm013m126a,
+        &'static mut [u8; FRAME_OCTETS],
+    >;
+
     pub struct DrawContext {
         pub circle: Circle,
         pub circle_style: PrimitiveStyle<Palette8>,
         pub background: Styled<Rectangle, PrimitiveStyle<Palette8>>,
         pub background_style: PrimitiveStyle<Palette8>,
+        pub title: TextBox<'static>,
     }
 
     #[local]
@@ -47,11 +63,20 @@ mod app {
     #[shared]
     struct Shared {
         #[lock_free]
-        jdi: lpm013m126a::Lpm013m126a<pac::SPIM3, hal::gpio::p0::P0_03<Output<PushPull>>>,
+        jdi: Jdi,
     }
 
-    #[init]
+    #[init(local = [
+        line_buffer: [u8; LINE_BUFFER_OCTETS] = [0; LINE_BUFFER_OCTETS],
+        front_buffer: [u8; FRAME_OCTETS] = [0; FRAME_OCTETS],
+        back_buffer: [u8; FRAME_OCTETS] = [0; FRAME_OCTETS],
+    ])]
     fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
+        // The cycle counter times the frames sent to the display
+        let mut core = cx.core;
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
         // Configure to use external clocks, and start them
         let _clocks = clocks::Clocks::new(cx.device.CLOCK)
             .enable_ext_hfosc()
@@ -75,7 +100,10 @@ mod app {
 
         let mut rtc_0 = match hal::rtc::Rtc::new(cx.device.RTC0, 4095) {
             Ok(r) => r,
-            Err(_) => unreachable!(),
+            Err(_) => {
+                defmt::error!("Failed to create RTC");
+                microbit_two::exit()
+            }
         };
         rtc_0.enable_event(hal::rtc::RtcInterrupt::Tick);
         rtc_0.enable_interrupt(hal::rtc::RtcInterrupt::Tick, None);
@@ -122,10 +150,19 @@ mod app {
             hal::spim::MODE_0,
             0,
         );
-        let mut jdi = lpm013m126a::Lpm013m126a::new(
+        let mut jdi = match Jdi::with_frame_buffers(
             jdi_spi,
             port0.p0_03.into_push_pull_output(gpio::Level::Low),
-        );
+            cx.local.line_buffer,
+            cx.local.front_buffer,
+            cx.local.back_buffer,
+        ) {
+            Ok(jdi) => jdi,
+            Err(_) => {
+                defmt::error!("Frame buffer storage does not fit the panel");
+                microbit_two::exit()
+            }
+        };
 
         let jdi_com = port1
             .p1_02
@@ -136,12 +173,9 @@ mod app {
             Err(_) => defmt::error!("Failed to initialize JDI"),
             Ok(_) => (),
         }
+        jdi.set_clock(DWT::cycle_count, 64_000_000);
 
-        for y in 0..DISPLAY_HEIGHT {
-            for x in 0..DISPLAY_WIDTH {
-                jdi.set_pixel(x, y, lpm013m126a::Palette8::Green);
-            }
-        }
+        jdi.fill(lpm013m126a::Palette8::Green);
 
         let circle = Circle::new(Point::new(22, 22), 20);
 
@@ -162,6 +196,16 @@ mod app {
             .stroke_color(Palette8::Cyan)
             .build();
 
+        let title = TextBox::new(
+            "micro:bit v2",
+            Rectangle::new(
+                Point::new(0, i32::from(DISPLAY_HEIGHT) - 20),
+                Size::new(u32::from(DISPLAY_WIDTH), 20),
+            ),
+            TextStyle::new(Font::Medium, Palette8::White).with_alignment(Alignment::Centre),
+        );
+        let _ = title.draw(&mut jdi);
+
         defmt::info!("Initialized");
 
         let shared = Shared { jdi };
@@ -177,6 +221,7 @@ mod app {
                 circle_style,
                 background,
                 background_style,
+                title,
             },
         };
         (shared, local, init::Monotonics())
@@ -202,8 +247,21 @@ mod app {
         }
     }
 
-    #[task(binds = TIMER2, local = [timer_2, draw_context], shared = [jdi])]
+    #[task(binds = TIMER2, local = [timer_2, draw_context, ticks: u32 = 0], shared = [jdi])]
     fn timer2(cx: timer2::Context) {
+        cx.local.timer_2.timer_reset_event();
+
+        // Log the frame timing once per second
+        *cx.local.ticks += 1;
+        if *cx.local.ticks % 24 == 0 {
+            defmt::info!("{}", cx.shared.jdi.stats());
+        }
+
+        // Wait for the previous frame to be sent
+        if cx.shared.jdi.is_pending() {
+            return;
+        }
+
         let ctx = cx.local.draw_context;
 
         let _ = ctx
@@ -220,10 +278,9 @@ mod app {
         }
 
         let _ = ctx.circle.into_styled(ctx.circle_style).draw(cx.shared.jdi);
+        let _ = ctx.title.draw(cx.shared.jdi);
 
         let _ = cx.shared.jdi.update_display();
-
-        cx.local.timer_2.timer_reset_event();
     }
 
     #[task(binds = RTC0, local = [rtc_0])]
@@ -236,6 +293,8 @@ mod app {
 
     #[task(binds = SPIM3, shared = [jdi])]
     fn display_spi(cx: display_spi::Context) {
-        cx.shared.jdi.spi_task_event();
+        if cx.shared.jdi.spi_task_event() {
+            defmt::trace!("Frame sent");
+        }
     }
 }
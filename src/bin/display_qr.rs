@@ -0,0 +1,150 @@
+#![no_main]
+#![no_std]
+
+use microbit_two::hal;
+use microbit_two::hal::pac;
+use rtic::app;
+
+#[app(device = pac, peripherals = true)]
+mod app {
+    use super::{hal, pac};
+
+    use embedded_graphics::{
+        geometry::{Point, Size},
+        primitives::Rectangle,
+        Drawable,
+    };
+    use hal::{
+        clocks,
+        gpio::{self, Output, PushPull},
+        gpiote::Gpiote,
+        ppi::{self, Ppi19},
+    };
+    use microbit_two::{
+        lpm013m126a::{
+            self, qr::ErrorCorrection, Alignment, Font, Palette8, QrCode, QrView, TextBox,
+            TextStyle, DISPLAY_HEIGHT, DISPLAY_WIDTH, LINE_BUFFER_OCTETS,
+        },
+        spim, Vcom,
+    };
+    use pac::RTC1;
+
+    const CAPTION_HEIGHT: u32 = 20;
+
+    #[local]
+    struct Local {
+        _vcom: Vcom<RTC1, Ppi19>,
+    }
+
+    #[shared]
+    struct Shared {
+        #[lock_free]
+        jdi: lpm013m126a::Lpm013m126a<pac::SPIM3, hal::gpio::p0::P0_03<Output<PushPull>>>,
+    }
+
+    #[init(local = [line_buffer: [u8; LINE_BUFFER_OCTETS] = [0; LINE_BUFFER_OCTETS]])]
+    fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
+        // The screen is static, use the low power RC oscillator for VCOM
+        let _clocks = clocks::Clocks::new(cx.device.CLOCK)
+            .set_lfclk_src_rc()
+            .start_lfclk();
+
+        let port0 = gpio::p0::Parts::new(cx.device.P0);
+        let port1 = gpio::p1::Parts::new(cx.device.P1);
+
+        let jdi_spi = spim::Spim::new(
+            cx.device.SPIM3,
+            spim::Pins {
+                sck: port0
+                    .p0_17
+                    .into_push_pull_output(gpio::Level::High)
+                    .degrade(),
+                mosi: Some(
+                    port0
+                        .p0_13
+                        .into_push_pull_output(gpio::Level::High)
+                        .degrade(),
+                ),
+                miso: None,
+                csn: Some(
+                    port0
+                        .p0_02
+                        .into_push_pull_output(gpio::Level::Low)
+                        .degrade(),
+                ),
+                csn_pol: true,
+            },
+            hal::spim::Frequency::M4,
+            hal::spim::MODE_0,
+            0,
+        );
+        let mut jdi = lpm013m126a::Lpm013m126a::new(
+            jdi_spi,
+            port0.p0_03.into_push_pull_output(gpio::Level::Low),
+            cx.local.line_buffer,
+        );
+
+        let jdi_com = port1
+            .p1_02
+            .into_push_pull_output(gpio::Level::Low)
+            .degrade();
+        let gpiote = Gpiote::new(cx.device.GPIOTE);
+        let ppi = ppi::Parts::new(cx.device.PPI);
+        let vcom = match Vcom::new(cx.device.RTC1, jdi_com, &gpiote.channel7(), ppi.ppi19, 1) {
+            Ok(vcom) => vcom,
+            Err(_) => unreachable!(),
+        };
+
+        match jdi.init() {
+            Err(_) => defmt::error!("Failed to initialize JDI"),
+            Ok(_) => (),
+        }
+
+        // Device identifier in upper case hexadecimal, for alphanumeric mode
+        let id = (cx.device.FICR.deviceid[1].read().bits() as u64) << 32
+            | cx.device.FICR.deviceid[0].read().bits() as u64;
+        let mut text = *b"MICROBIT-0000000000000000";
+        for (i, c) in text[9..].iter_mut().enumerate() {
+            let nibble = (id >> (60 - 4 * i)) & 0xf;
+            *c = b"0123456789ABCDEF"[nibble as usize];
+        }
+        let text = core::str::from_utf8(&text).unwrap_or("MICROBIT");
+
+        jdi.fill(Palette8::White);
+        match QrCode::encode_text(text, ErrorCorrection::Medium) {
+            Ok(code) => {
+                let area = Rectangle::new(
+                    Point::zero(),
+                    Size::new(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32 - CAPTION_HEIGHT),
+                );
+                let _ = QrView::fit(&code, area).draw(&mut jdi);
+                defmt::info!("QR code version {}", code.version());
+            }
+            Err(_) => defmt::error!("Failed to encode QR code"),
+        }
+        let _ = TextBox::new(
+            text,
+            Rectangle::new(
+                Point::new(0, (DISPLAY_HEIGHT as u32 - CAPTION_HEIGHT) as i32),
+                Size::new(DISPLAY_WIDTH as u32, CAPTION_HEIGHT),
+            ),
+            TextStyle::new(Font::Small, Palette8::Black).with_alignment(Alignment::Centre),
+        )
+        .draw(&mut jdi);
+        let _ = jdi.update_display();
+        // Send the frame, then disable the SPIM
+        jdi.flush();
+        jdi.hold();
+
+        defmt::info!("Initialized");
+
+        let shared = Shared { jdi };
+        let local = Local { _vcom: vcom };
+        (shared, local, init::Monotonics())
+    }
+
+    #[task(binds = SPIM3, shared = [jdi])]
+    fn display_spi(cx: display_spi::Context) {
+        cx.shared.jdi.spi_task_event();
+    }
+}
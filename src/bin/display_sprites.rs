@@ -0,0 +1,294 @@
+#![no_main]
+#![no_std]
+
+use microbit_two::hal;
+use microbit_two::hal::pac;
+use rtic::app;
+
+#[app(device = pac, peripherals = true)]
+mod app {
+    use super::{hal, pac};
+
+    use embedded_hal::digital::v2::{OutputPin, StatefulOutputPin};
+    use hal::{
+        clocks,
+        gpio::{self, Output, PushPull},
+        timer::Instance,
+    };
+    use microbit_two::{
+        lpm013m126a::{
+            self, panel, sprite::SpriteId, Palette8, Panel, Scene, Sprite, TileSheet,
+            LINE_BUFFER_OCTETS,
+        },
+        spim,
+    };
+    use pac::{TIMER1, TIMER2};
+
+    const TILE: u16 = 8;
+    const SHEET_WIDTH: u16 = 4 * TILE;
+    // Two rows of four tiles, two pixels per octet
+    const SHEET_OCTETS: usize = (SHEET_WIDTH * 2 * TILE / 2) as usize;
+    const COLUMNS: usize = (panel::Lpm013m126a::WIDTH / TILE) as usize;
+    const ROWS: usize = (panel::Lpm013m126a::HEIGHT / TILE) as usize;
+    const SPRITES: usize = 6;
+
+    const GRASS: u8 = 0;
+    const WATER: u8 = 1;
+    const BRICK: u8 = 2;
+    const SAND: u8 = 3;
+    const BALL: u16 = 4;
+    const FISH: u16 = 5;
+
+    type Jdi = lpm013m126a::LineDisplay<
+        pac::SPIM3,
+        hal::gpio::p0::P0_03<Output<PushPull>>,
+        panel::Lpm013m126a,
+        Scene<'static, COLUMNS, ROWS, SPRITES>,
+    >;
+
+    /// A sprite moving with constant speed, bouncing at the display edges
+    pub struct Mover {
+        id: SpriteId,
+        dx: i16,
+        dy: i16,
+    }
+
+    /// Colour index of a pixel of the tile sheet
+    fn sheet_pixel(tile: u16, x: u16, y: u16) -> Palette8 {
+        match tile as u8 {
+            GRASS if (x * 3 + y * 5) % 7 == 0 => Palette8::Yellow,
+            GRASS => Palette8::Green,
+            WATER if (x + 2 * (y / 4)) % 8 < 2 && y % 4 == 0 => Palette8::White,
+            WATER => Palette8::Blue,
+            BRICK if y % 4 == 3 || (x + 4 * ((y / 4) % 2)) % 8 == 0 => Palette8::White,
+            BRICK => Palette8::Red,
+            SAND => Palette8::Yellow,
+            _ => match tile {
+                BALL => {
+                    let (dx, dy) = (2 * x as i16 - 7, 2 * y as i16 - 7);
+                    match dx * dx + dy * dy {
+                        d if d < 12 => Palette8::White,
+                        d if d < 56 => Palette8::Red,
+                        _ => Palette8::Pink,
+                    }
+                }
+                FISH => match (x, y) {
+                    (6, 3) => Palette8::Black,
+                    (0..=1, 2..=5) | (2..=7, 1..=6) => Palette8::Cyan,
+                    _ => Palette8::Pink,
+                },
+                _ => Palette8::Pink,
+            },
+        }
+    }
+
+    #[local]
+    struct Local {
+        timer_1: TIMER1,
+        timer_2: TIMER2,
+        jdi_com: hal::gpio::Pin<hal::gpio::Output<hal::gpio::PushPull>>,
+        movers: [Mover; SPRITES],
+    }
+
+    #[shared]
+    struct Shared {
+        #[lock_free]
+        jdi: Jdi,
+    }
+
+    #[init(local = [
+        line_buffer: [u8; LINE_BUFFER_OCTETS] = [0; LINE_BUFFER_OCTETS],
+        sheet: [u8; SHEET_OCTETS] = [0; SHEET_OCTETS],
+    ])]
+    fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
+        // Configure to use external clocks, and start them
+        let _clocks = clocks::Clocks::new(cx.device.CLOCK)
+            .enable_ext_hfosc()
+            .set_lfclk_src_synth()
+            .start_lfclk();
+
+        let port0 = gpio::p0::Parts::new(cx.device.P0);
+        let port1 = gpio::p1::Parts::new(cx.device.P1);
+
+        cx.device.TIMER1.set_periodic();
+        cx.device.TIMER1.enable_interrupt();
+        cx.device.TIMER1.timer_start(1_000_000_u32);
+
+        cx.device.TIMER2.set_periodic();
+        cx.device.TIMER2.enable_interrupt();
+        cx.device.TIMER2.timer_start(41_666_u32);
+
+        // Draw the tile sheet procedurally instead of storing it in flash
+        for (i, octet) in cx.local.sheet.iter_mut().enumerate() {
+            let x = (i * 2) as u16 % SHEET_WIDTH;
+            let y = (i * 2) as u16 / SHEET_WIDTH;
+            let tile = (y / TILE) * (SHEET_WIDTH / TILE) + x / TILE;
+            let left = sheet_pixel(tile, x % TILE, y % TILE);
+            let right = sheet_pixel(tile, x % TILE + 1, y % TILE);
+            *octet = u8::from(left) << 4 | u8::from(right);
+        }
+        let sheet = match TileSheet::new(&cx.local.sheet[..], SHEET_WIDTH, TILE, TILE) {
+            Ok(sheet) => sheet,
+            Err(_) => unreachable!(),
+        };
+
+        let mut scene = Scene::new(sheet, Palette8::Black);
+        for row in 0..ROWS {
+            for column in 0..COLUMNS {
+                let tile = if row == 0 || row == ROWS - 1 || column == 0 || column == COLUMNS - 1 {
+                    BRICK
+                } else if (8..14).contains(&row) && (4..18).contains(&column) {
+                    WATER
+                } else if row == 7 || row == 14 {
+                    SAND
+                } else {
+                    GRASS
+                };
+                let _ = scene.set_tile(column, row, tile);
+            }
+        }
+
+        let mut add = |tile: u16, x: i16, y: i16, z: i8| {
+            let sprite = Sprite::new(sheet, tile)
+                .with_key(Palette8::Pink)
+                .with_position(x, y)
+                .with_z(z);
+            match scene.add_sprite(sprite) {
+                Ok(id) => id,
+                Err(_) => unreachable!(),
+            }
+        };
+        let movers = [
+            Mover {
+                id: add(BALL, 20, 30, 1),
+                dx: 2,
+                dy: 1,
+            },
+            Mover {
+                id: add(BALL, 100, 40, 1),
+                dx: -1,
+                dy: 2,
+            },
+            Mover {
+                id: add(BALL, 60, 120, 2),
+                dx: 3,
+                dy: -2,
+            },
+            Mover {
+                id: add(BALL, 140, 150, 2),
+                dx: -2,
+                dy: -3,
+            },
+            Mover {
+                id: add(FISH, 40, 80, 0),
+                dx: 1,
+                dy: 0,
+            },
+            Mover {
+                id: add(FISH, 120, 96, 0),
+                dx: -2,
+                dy: 0,
+            },
+        ];
+
+        let jdi_spi = spim::Spim::new(
+            cx.device.SPIM3,
+            spim::Pins {
+                sck: port0
+                    .p0_17
+                    .into_push_pull_output(gpio::Level::High)
+                    .degrade(),
+                mosi: Some(
+                    port0
+                        .p0_13
+                        .into_push_pull_output(gpio::Level::High)
+                        .degrade(),
+                ),
+                miso: None,
+                csn: Some(
+                    port0
+                        .p0_02
+                        .into_push_pull_output(gpio::Level::Low)
+                        .degrade(),
+                ),
+                csn_pol: true,
+            },
+            hal::spim::Frequency::M4,
+            hal::spim::MODE_0,
+            0,
+        );
+        let mut jdi = Jdi::new(
+            jdi_spi,
+            port0.p0_03.into_push_pull_output(gpio::Level::Low),
+            cx.local.line_buffer,
+            scene,
+        );
+
+        let jdi_com = port1
+            .p1_02
+            .into_push_pull_output(gpio::Level::Low)
+            .degrade();
+
+        match jdi.init() {
+            Err(_) => defmt::error!("Failed to initialize JDI"),
+            Ok(_) => (),
+        }
+        let _ = jdi.update_display();
+
+        defmt::info!("Initialized");
+
+        let shared = Shared { jdi };
+        let local = Local {
+            timer_1: cx.device.TIMER1,
+            timer_2: cx.device.TIMER2,
+            jdi_com,
+            movers,
+        };
+        (shared, local, init::Monotonics())
+    }
+
+    #[task(binds = TIMER1, local = [timer_1, jdi_com])]
+    fn timer1(cx: timer1::Context) {
+        cx.local.timer_1.timer_reset_event();
+        let high = match cx.local.jdi_com.is_set_high() {
+            Ok(s) => s,
+            Err(_) => false,
+        };
+        if high {
+            let _ = cx.local.jdi_com.set_low();
+        } else {
+            let _ = cx.local.jdi_com.set_high();
+        }
+    }
+
+    #[task(binds = TIMER2, local = [timer_2, movers], shared = [jdi])]
+    fn timer2(cx: timer2::Context) {
+        cx.local.timer_2.timer_reset_event();
+        let scene = cx.shared.jdi.renderer_mut();
+        let right = (panel::Lpm013m126a::WIDTH - TILE) as i16;
+        let bottom = (panel::Lpm013m126a::HEIGHT - TILE) as i16;
+        for mover in cx.local.movers.iter_mut() {
+            let (dx, dy) = (&mut mover.dx, &mut mover.dy);
+            let _ = scene.update_sprite(mover.id, |sprite| {
+                let x = sprite.x + *dx;
+                let y = sprite.y + *dy;
+                if x < 0 || x > right {
+                    *dx = -*dx;
+                }
+                if y < 0 || y > bottom {
+                    *dy = -*dy;
+                }
+                sprite.x = x.clamp(0, right);
+                sprite.y = y.clamp(0, bottom);
+                // Fish face the direction they swim
+                sprite.flip_horizontal = *dx < 0;
+            });
+        }
+        let _ = cx.shared.jdi.update_display();
+    }
+
+    #[task(binds = SPIM3, shared = [jdi])]
+    fn display_spi(cx: display_spi::Context) {
+        cx.shared.jdi.spi_task_event();
+    }
+}
@@ -0,0 +1,351 @@
+#![no_main]
+#![no_std]
+
+use microbit_two::hal;
+use microbit_two::hal::pac;
+use rtic::app;
+
+#[app(device = pac, peripherals = true)]
+mod app {
+    use super::{hal, pac};
+
+    use embedded_graphics::geometry::{Point, Size};
+    use embedded_graphics::primitives::Rectangle;
+    use embedded_hal::digital::v2::{InputPin, OutputPin, StatefulOutputPin};
+    use hal::{
+        clocks,
+        gpio::{self, Floating, Input, Output, PushPull},
+        timer::Instance,
+    };
+    use microbit_two::{
+        lpm013m126a::{
+            self,
+            screenshot::Capture,
+            widget::{
+                draw_dirty, Checkbox, Direction, Focus, Icon, IconButton, Label, List, ProgressBar,
+                Stack, StatusBar, Value,
+            },
+            Alignment, Palette8, Theme, Widget, LINE_BUFFER_OCTETS,
+        },
+        spim,
+    };
+    use pac::{RTC0, TIMER0, TIMER1, TIMER2};
+
+    type Jdi = lpm013m126a::Lpm013m126a<pac::SPIM3, hal::gpio::p0::P0_03<Output<PushPull>>>;
+
+    // Screenshot lines logged per timer 2 tick
+    const SCREENSHOT_LINES: u16 = 8;
+
+    const HEART: [u8; 20] = [
+        0b0110_0011,
+        0b0000_0000,
+        0b1111_0111,
+        0b1000_0000,
+        0b1111_1111,
+        0b1000_0000,
+        0b1111_1111,
+        0b1000_0000,
+        0b0111_1111,
+        0b0000_0000,
+        0b0011_1110,
+        0b0000_0000,
+        0b0001_1100,
+        0b0000_0000,
+        0b0000_1000,
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+    ];
+
+    const ITEMS: [&str; 5] = [
+        "Accelerometer",
+        "Compass",
+        "Microphone",
+        "Speaker",
+        "Thermometer",
+    ];
+
+    pub struct Ui {
+        pub theme: Theme,
+        pub status: StatusBar<'static>,
+        pub title: Label<'static>,
+        pub uptime: Value<'static>,
+        pub progress: ProgressBar,
+        pub like: IconButton<'static>,
+        pub dark: Checkbox<'static>,
+        pub sensors: List<'static>,
+    }
+
+    impl Ui {
+        pub fn widgets(&mut self) -> [&mut dyn Widget<Jdi>; 7] {
+            [
+                &mut self.status,
+                &mut self.title,
+                &mut self.uptime,
+                &mut self.progress,
+                &mut self.like,
+                &mut self.dark,
+                &mut self.sensors,
+            ]
+        }
+    }
+
+    pub struct Buttons {
+        pub a: hal::gpio::p0::P0_14<Input<Floating>>,
+        pub b: hal::gpio::p0::P0_23<Input<Floating>>,
+        pub a_pressed: bool,
+        pub b_pressed: bool,
+    }
+
+    #[local]
+    struct Local {
+        rtc_0: hal::rtc::Rtc<RTC0>,
+        timer_0: TIMER0,
+        timer_1: TIMER1,
+        timer_2: TIMER2,
+        led_matrix: microbit_two::LedMatrix,
+        jdi_com: hal::gpio::Pin<hal::gpio::Output<hal::gpio::PushPull>>,
+        ui: Ui,
+        focus: Focus,
+        buttons: Buttons,
+        ticks: u32,
+        screenshot: Option<Capture>,
+    }
+
+    #[shared]
+    struct Shared {
+        #[lock_free]
+        jdi: Jdi,
+    }
+
+    #[init(local = [line_buffer: [u8; LINE_BUFFER_OCTETS] = [0; LINE_BUFFER_OCTETS]])]
+    fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
+        // Configure to use external clocks, and start them
+        let _clocks = clocks::Clocks::new(cx.device.CLOCK)
+            .enable_ext_hfosc()
+            .set_lfclk_src_synth()
+            .start_lfclk();
+
+        let port0 = gpio::p0::Parts::new(cx.device.P0);
+        let port1 = gpio::p1::Parts::new(cx.device.P1);
+
+        cx.device.TIMER0.set_periodic();
+        cx.device.TIMER0.enable_interrupt();
+        cx.device.TIMER0.timer_start(160_u32);
+
+        cx.device.TIMER1.set_periodic();
+        cx.device.TIMER1.enable_interrupt();
+        cx.device.TIMER1.timer_start(1_000_000_u32);
+
+        cx.device.TIMER2.set_periodic();
+        cx.device.TIMER2.enable_interrupt();
+        cx.device.TIMER2.timer_start(41_666_u32);
+
+        let mut rtc_0 = match hal::rtc::Rtc::new(cx.device.RTC0, 4095) {
+            Ok(r) => r,
+            Err(_) => unreachable!(),
+        };
+        rtc_0.enable_event(hal::rtc::RtcInterrupt::Tick);
+        rtc_0.enable_interrupt(hal::rtc::RtcInterrupt::Tick, None);
+        rtc_0.enable_counter();
+
+        let mut led_matrix = microbit_two::LedMatrix::new(
+            port0.p0_28.into_push_pull_output(gpio::Level::Low),
+            port0.p0_11.into_push_pull_output(gpio::Level::Low),
+            port0.p0_31.into_push_pull_output(gpio::Level::Low),
+            port1.p1_05.into_push_pull_output(gpio::Level::Low),
+            port0.p0_30.into_push_pull_output(gpio::Level::Low),
+            port0.p0_21.into_push_pull_output(gpio::Level::Low),
+            port0.p0_22.into_push_pull_output(gpio::Level::Low),
+            port0.p0_15.into_push_pull_output(gpio::Level::Low),
+            port0.p0_24.into_push_pull_output(gpio::Level::Low),
+            port0.p0_19.into_push_pull_output(gpio::Level::Low),
+        );
+
+        led_matrix.display(microbit_two::images::SCALES);
+
+        let jdi_spi = spim::Spim::new(
+            cx.device.SPIM3,
+            spim::Pins {
+                sck: port0
+                    .p0_17
+                    .into_push_pull_output(gpio::Level::High)
+                    .degrade(),
+                mosi: Some(
+                    port0
+                        .p0_13
+                        .into_push_pull_output(gpio::Level::High)
+                        .degrade(),
+                ),
+                miso: None,
+                csn: Some(
+                    port0
+                        .p0_02
+                        .into_push_pull_output(gpio::Level::Low)
+                        .degrade(),
+                ),
+                csn_pol: true,
+            },
+            hal::spim::Frequency::M4,
+            hal::spim::MODE_0,
+            0,
+        );
+        let mut jdi = lpm013m126a::Lpm013m126a::new(
+            jdi_spi,
+            port0.p0_03.into_push_pull_output(gpio::Level::Low),
+            cx.local.line_buffer,
+        );
+
+        let jdi_com = port1
+            .p1_02
+            .into_push_pull_output(gpio::Level::Low)
+            .degrade();
+
+        match jdi.init() {
+            Err(_) => defmt::error!("Failed to initialize JDI"),
+            Ok(_) => (),
+        }
+
+        let mut ui = Ui {
+            theme: Theme::default(),
+            status: StatusBar::new("micro:bit", "A next B select"),
+            title: Label::new("Widgets").with_alignment(Alignment::Centre),
+            uptime: Value::new("Uptime", " s", 1),
+            progress: ProgressBar::new(100),
+            like: IconButton::new(Icon::new(9, 10, &HEART), "Like"),
+            dark: Checkbox::new("Dark theme", true),
+            sensors: List::new(&ITEMS),
+        };
+        let area = Rectangle::new(
+            Point::zero(),
+            Size::new(
+                u32::from(lpm013m126a::DISPLAY_WIDTH),
+                u32::from(lpm013m126a::DISPLAY_HEIGHT),
+            ),
+        );
+        let theme = ui.theme;
+        Stack::new(area, Direction::Column)
+            .with_spacing(2)
+            .arrange(&mut ui.widgets(), &theme);
+
+        let buttons = Buttons {
+            a: port0.p0_14.into_floating_input(),
+            b: port0.p0_23.into_floating_input(),
+            a_pressed: false,
+            b_pressed: false,
+        };
+
+        defmt::info!("Initialized");
+
+        let shared = Shared { jdi };
+        let local = Local {
+            timer_0: cx.device.TIMER0,
+            timer_1: cx.device.TIMER1,
+            timer_2: cx.device.TIMER2,
+            rtc_0,
+            led_matrix,
+            jdi_com,
+            ui,
+            focus: Focus::new(),
+            buttons,
+            ticks: 0,
+            screenshot: None,
+        };
+        (shared, local, init::Monotonics())
+    }
+
+    #[task(binds = TIMER0, local = [timer_0, led_matrix])]
+    fn timer0(cx: timer0::Context) {
+        cx.local.timer_0.timer_reset_event();
+        cx.local.led_matrix.update();
+    }
+
+    #[task(binds = TIMER1, local = [timer_1, jdi_com])]
+    fn timer1(cx: timer1::Context) {
+        cx.local.timer_1.timer_reset_event();
+        let high = match cx.local.jdi_com.is_set_high() {
+            Ok(s) => s,
+            Err(_) => false,
+        };
+        if high {
+            let _ = cx.local.jdi_com.set_low();
+        } else {
+            let _ = cx.local.jdi_com.set_high();
+        }
+    }
+
+    #[task(binds = TIMER2, local = [timer_2, ui, focus, buttons, ticks, screenshot], shared = [jdi])]
+    fn timer2(cx: timer2::Context) {
+        cx.local.timer_2.timer_reset_event();
+        let ui = cx.local.ui;
+        let buttons = cx.local.buttons;
+
+        // The buttons are active low, act when pressed
+        let a = buttons.a.is_low().unwrap_or(false);
+        let b = buttons.b.is_low().unwrap_or(false);
+        if a && !buttons.a_pressed {
+            cx.local.focus.next(&mut ui.widgets());
+        }
+        if b && !buttons.b_pressed {
+            cx.local.focus.activate(&mut ui.widgets());
+        }
+        // Both buttons log a screenshot, see the `screenshot` example
+        if a && b && !(buttons.a_pressed && buttons.b_pressed) && cx.local.screenshot.is_none() {
+            *cx.local.screenshot = Some(Capture::new());
+        }
+        // A few lines per tick, to keep the display interrupt going
+        if let Some(capture) = cx.local.screenshot {
+            if cx.shared.jdi.log_screenshot(capture, SCREENSHOT_LINES) {
+                *cx.local.screenshot = None;
+            }
+        }
+        buttons.a_pressed = a;
+        buttons.b_pressed = b;
+
+        let dark = ui.dark.is_checked();
+        if dark != (ui.theme.background == Palette8::Black) {
+            ui.theme = if dark {
+                Theme::default()
+            } else {
+                Theme {
+                    foreground: Palette8::Black,
+                    background: Palette8::White,
+                    accent: Palette8::Blue,
+                    ..Theme::default()
+                }
+            };
+            for widget in ui.widgets().iter_mut() {
+                widget.mark_dirty();
+            }
+        }
+
+        if ui.like.take_pressed() {
+            defmt::info!("Liked {}", ui.sensors.selected());
+        }
+
+        // Timer 2 runs at 24 Hz
+        *cx.local.ticks += 1;
+        ui.uptime.set_value((*cx.local.ticks * 10 / 24) as i32);
+        ui.progress.set_value(*cx.local.ticks % 101);
+
+        let theme = ui.theme;
+        if let Ok(true) = draw_dirty(&mut ui.widgets(), cx.shared.jdi, &theme) {
+            let _ = cx.shared.jdi.update_display();
+        }
+    }
+
+    #[task(binds = RTC0, local = [rtc_0])]
+    fn rtc(cx: rtc::Context) {
+        let _ = cx
+            .local
+            .rtc_0
+            .is_event_triggered(hal::rtc::RtcInterrupt::Tick);
+    }
+
+    #[task(binds = SPIM3, shared = [jdi])]
+    fn display_spi(cx: display_spi::Context) {
+        cx.shared.jdi.spi_task_event();
+    }
+}
@@ -58,7 +58,8 @@ mod app {
             port0.p0_19.into_push_pull_output(gpio::Level::Low),
         );
 
-        led_matrix.display(microbit_two::images::SCALES);
+        // Brightness levels from the luma of each pixel
+        led_matrix.display(microbit_two::include_matrix_image!("assets/glow.png"));
 
         (
             Shared {},
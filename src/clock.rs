@@ -0,0 +1,130 @@
+//! # Time of day kept by an RTC
+//!
+//! [`Clock`] counts the low frequency clock at 8 Hz with an RTC. The compare
+//! event interrupts once per interval, such as every second or minute,
+//! aligned to the time of day, so a clock face is redrawn only when the time
+//! shown changes. The overflow of the 24 bit counter, every 24 days, is
+//! handled by the same interrupt.
+//!
+//! ```ignore
+//! let mut clock = Clock::new(cx.device.RTC0, DateTime::new(2022, 1, 1, 12, 0, 0))?;
+//!
+//! #[task(binds = RTC0, local = [clock])]
+//! fn rtc(cx: rtc::Context) {
+//!     if cx.local.clock.handle_interrupt() {
+//!         let now = cx.local.clock.now();
+//!     }
+//! }
+//! ```
+
+use crate::{
+    hal::rtc::{Instance, Rtc, RtcCompareReg, RtcInterrupt},
+    time::DateTime,
+    Error,
+};
+
+// 32768 Hz / (4095 + 1) = 8 Hz
+const PRESCALER: u32 = 4095;
+const TICKS_PER_SECOND: u32 = 8;
+const COUNTER_MASK: u32 = 0x00ff_ffff;
+const OVERFLOW_SECONDS: u32 = (COUNTER_MASK + 1) / TICKS_PER_SECOND;
+
+/// Date and time counted by an RTC
+pub struct Clock<R: Instance> {
+    rtc: Rtc<R>,
+    // seconds at counter zero
+    base: u32,
+    // seconds between interrupts
+    interval: u32,
+}
+
+impl<R> Clock<R>
+where
+    R: Instance,
+{
+    /// Start counting from `time`, interrupting every second
+    ///
+    /// The low frequency clock must be running and the RTC must not be used
+    /// for other purposes.
+    pub fn new(rtc: R, time: DateTime) -> Result<Self, Error> {
+        let mut rtc = Rtc::new(rtc, PRESCALER).map_err(|_| Error::UnsupportedError)?;
+        rtc.enable_event(RtcInterrupt::Overflow);
+        rtc.enable_interrupt(RtcInterrupt::Overflow, None);
+        rtc.enable_event(RtcInterrupt::Compare0);
+        rtc.enable_interrupt(RtcInterrupt::Compare0, None);
+        let mut clock = Self {
+            rtc,
+            base: 0,
+            interval: 1,
+        };
+        clock.set(time);
+        clock.rtc.enable_counter();
+        Ok(clock)
+    }
+
+    /// Current date and time
+    pub fn now(&self) -> DateTime {
+        DateTime::from_seconds(self.seconds())
+    }
+
+    /// Seconds since 2000-01-01 00:00:00
+    pub fn seconds(&self) -> u32 {
+        let counter = self.rtc.get_counter();
+        // An overflow not yet handled, unless the counter was read before it
+        let overflowed =
+            self.rtc.is_event_triggered(RtcInterrupt::Overflow) && counter < COUNTER_MASK / 2;
+        let base = if overflowed {
+            self.base.wrapping_add(OVERFLOW_SECONDS)
+        } else {
+            self.base
+        };
+        base.wrapping_add(counter / TICKS_PER_SECOND)
+    }
+
+    /// Set the date and time
+    pub fn set(&mut self, time: DateTime) {
+        self.rtc.clear_counter();
+        self.rtc.reset_event(RtcInterrupt::Overflow);
+        self.base = time.to_seconds();
+        self.schedule();
+    }
+
+    /// Interrupt every `seconds`, 60 interrupts when the minute changes
+    pub fn set_interval(&mut self, seconds: u32) {
+        self.interval = seconds.max(1);
+        self.schedule();
+    }
+
+    /// Handle the RTC interrupt, returns `true` at the start of an interval
+    pub fn handle_interrupt(&mut self) -> bool {
+        if self.rtc.is_event_triggered(RtcInterrupt::Overflow) {
+            self.rtc.reset_event(RtcInterrupt::Overflow);
+            self.base = self.base.wrapping_add(OVERFLOW_SECONDS);
+        }
+        if self.rtc.is_event_triggered(RtcInterrupt::Compare0) {
+            self.rtc.reset_event(RtcInterrupt::Compare0);
+            self.schedule();
+            return true;
+        }
+        false
+    }
+
+    /// Stop counting and release the RTC
+    pub fn release(self) -> R {
+        self.rtc.disable_counter();
+        self.rtc.release()
+    }
+
+    /// Set the compare event at the start of the next interval
+    fn schedule(&mut self) {
+        let counter = self.rtc.get_counter();
+        let mut next = (self.seconds() / self.interval + 1) * self.interval;
+        // The counter must be at least two ticks before the compare value
+        let ticks = |next: u32| next.wrapping_sub(self.base).wrapping_mul(TICKS_PER_SECOND);
+        if ticks(next).wrapping_sub(counter) & COUNTER_MASK < 2 {
+            next += self.interval;
+        }
+        let compare = ticks(next) & COUNTER_MASK;
+        let _ = self.rtc.set_compare(RtcCompareReg::Compare0, compare);
+    }
+}
@@ -7,4 +7,14 @@ pub enum Error {
     DisplayError,
     /// Attempted to write to a non-existing pixel outside the display's bounds
     OutOfBoundsError,
+    /// The operation is not supported by the device
+    UnsupportedError,
+    /// The provided buffer does not have a supported size
+    BufferSizeError,
+    /// The data is not in the expected format
+    FormatError,
+    /// The data does not fit
+    CapacityError,
+    /// The device is busy with another operation
+    BusyError,
 }
@@ -1,19 +1,34 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "hardware")]
 use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "hardware")]
 pub use nrf52833_hal as hal;
 
+#[cfg(feature = "hardware")]
+pub mod clock;
 mod error;
+#[cfg(feature = "hardware")]
 pub mod images;
+#[cfg(feature = "hardware")]
 mod led_matrix;
 pub mod lpm013m126a;
+#[cfg(feature = "hardware")]
 pub mod spim;
+pub mod time;
+#[cfg(feature = "hardware")]
+pub mod vcom;
 
 pub use error::Error;
+#[cfg(feature = "macros")]
+pub use microbit_two_macros::{include_matrix_image, include_palette8_image};
 
+#[cfg(feature = "hardware")]
 use defmt_rtt as _; // global logger
+#[cfg(feature = "hardware")]
 use panic_probe as _;
 
+#[cfg(feature = "hardware")]
 defmt::timestamp! {"{=u64}", {
         static COUNT: AtomicUsize = AtomicUsize::new(0);
         // NOTE(no-CAS) `timestamps` runs with interrupts disabled
@@ -24,22 +39,32 @@ defmt::timestamp! {"{=u64}", {
 }
 
 /// Terminates the application and makes `probe-run` exit with exit-code = 0
+#[cfg(feature = "hardware")]
 pub fn exit() -> ! {
     loop {
         cortex_m::asm::bkpt();
     }
 }
 
+#[cfg(feature = "hardware")]
+pub use clock::Clock;
+#[cfg(feature = "hardware")]
 pub use led_matrix::{Image, LedMatrix};
+pub use time::DateTime;
+#[cfg(feature = "hardware")]
+pub use vcom::Vcom;
 
+#[cfg(feature = "hardware")]
 use crate::hal::target_constants;
 /// Does this slice reside entirely within RAM?
+#[cfg(feature = "hardware")]
 pub(crate) fn slice_in_ram(slice: &[u8]) -> bool {
     let ptr = slice.as_ptr() as usize;
     ptr >= target_constants::SRAM_LOWER && (ptr + slice.len()) < target_constants::SRAM_UPPER
 }
 
 /// Return an error if slice is not in RAM.
+#[cfg(feature = "hardware")]
 pub(crate) fn slice_in_ram_or<T>(slice: &[u8], err: T) -> Result<(), T> {
     if slice_in_ram(slice) {
         Ok(())
@@ -49,12 +74,15 @@ pub(crate) fn slice_in_ram_or<T>(slice: &[u8], err: T) -> Result<(), T> {
 }
 /// A handy structure for converting rust slices into ptr and len pairs
 /// for use with EasyDMA. Care must be taken to make sure mutability
-/// guarantees are respected
+/// guarantees are respected, and that the slice outlives the transfer,
+/// see `Spim::start_spi_dma_transfer`
+#[cfg(feature = "hardware")]
 pub(crate) struct DmaSlice {
     ptr: u32,
     len: u32,
 }
 
+#[cfg(feature = "hardware")]
 impl DmaSlice {
     pub fn null() -> Self {
         Self { ptr: 0, len: 0 }
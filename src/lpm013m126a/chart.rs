@@ -0,0 +1,468 @@
+//! # Charts of sample streams
+//!
+//! [`Chart`] plots the last `N` samples of `SERIES` series, such as
+//! accelerometer axes or a microphone level, each series in its own colour.
+//! The samples are kept in a [`RingBuffer`] per series.
+//!
+//! The Y axis is either scaled to the samples shown or fixed. In scroll mode
+//! the newest sample is at the right edge and older samples move left. In
+//! sweep mode, as on an oscilloscope, a new sample replaces the oldest one at
+//! a cursor moving to the right.
+//!
+//! Drawing is incremental, only the line segments that moved are erased and
+//! the grid and traces are redrawn in their columns. In sweep mode with a
+//! fixed scale each new sample only touches the columns next to the cursor.
+//! The redrawn columns span the chart height, drawn to a
+//! [`FrameBuffer`](crate::lpm013m126a::FrameBuffer) the lines sent are
+//! those the redrawn segments and grid lines cross, up to the full chart
+//! height for a steep trace.
+//!
+//! In scroll mode every sample moves left with each new sample, as does
+//! every sample when the automatic scale changes. Nearly every segment then
+//! moves and the whole chart is redrawn and sent.
+//!
+//! A chart is a [`Widget`] and can be placed by a
+//! [`Stack`](crate::lpm013m126a::widget::Stack).
+
+use crate::lpm013m126a::{
+    widget::{State, Theme, Widget},
+    Palette8,
+};
+use embedded_graphics::{
+    draw_target::{DrawTarget, DrawTargetExt},
+    geometry::{Point, Size},
+    primitives::{Line, Primitive, PrimitiveStyle, Rectangle},
+    Drawable, Pixel,
+};
+
+/// Fixed capacity buffer dropping the oldest element when full
+pub struct RingBuffer<T, const N: usize> {
+    data: [T; N],
+    // index of the oldest element
+    start: usize,
+    length: usize,
+}
+
+impl<T, const N: usize> Default for RingBuffer<T, N>
+where
+    T: Copy + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> RingBuffer<T, N>
+where
+    T: Copy + Default,
+{
+    pub fn new() -> Self {
+        Self {
+            data: [T::default(); N],
+            start: 0,
+            length: 0,
+        }
+    }
+
+    /// Add an element, dropping the oldest element when full
+    pub fn push(&mut self, value: T) {
+        if N == 0 {
+            return;
+        }
+        if self.length < N {
+            self.data[(self.start + self.length) % N] = value;
+            self.length += 1;
+        } else {
+            self.data[self.start] = value;
+            self.start = (self.start + 1) % N;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.length == N
+    }
+
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.length = 0;
+    }
+
+    /// Element `index`, counted from the oldest element
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index < self.length {
+            Some(self.data[(self.start + index) % N])
+        } else {
+            None
+        }
+    }
+
+    pub fn newest(&self) -> Option<T> {
+        self.length.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    /// The elements from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.length).map(move |index| self.data[(self.start + index) % N])
+    }
+}
+
+/// Range of the Y axis
+#[derive(Clone, Copy, PartialEq)]
+pub enum Scale {
+    /// The minimum and maximum of the samples shown
+    Auto,
+    /// Fixed range, samples outside are drawn at the edge
+    Fixed { min: i32, max: i32 },
+}
+
+/// Placement of new samples
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Newest sample at the right edge
+    Scroll,
+    /// Newest sample at a cursor sweeping from left to right
+    Sweep,
+}
+
+/// Chart of `SERIES` series of `N` samples
+pub struct Chart<const SERIES: usize, const N: usize> {
+    state: State,
+    series: [RingBuffer<i32, N>; SERIES],
+    colours: [Palette8; SERIES],
+    // Y coordinate drawn at each sample position
+    drawn: [[Option<i16>; N]; SERIES],
+    // bounds the drawn positions are for
+    drawn_bounds: Rectangle,
+    // samples pushed, for the sweep cursor
+    count: usize,
+    scale: Scale,
+    mode: Mode,
+    grid: u8,
+}
+
+impl<const SERIES: usize, const N: usize> Chart<SERIES, N> {
+    /// Scrolling line chart with automatic scale and four grid divisions
+    pub fn new(colours: [Palette8; SERIES]) -> Self {
+        Self {
+            state: State::default(),
+            series: [(); SERIES].map(|_| RingBuffer::new()),
+            colours,
+            drawn: [[None; N]; SERIES],
+            drawn_bounds: Rectangle::zero(),
+            count: 0,
+            scale: Scale::Auto,
+            mode: Mode::Scroll,
+            grid: 4,
+        }
+    }
+
+    /// Oscilloscope view sweeping over a fixed range with grid
+    pub fn oscilloscope(colours: [Palette8; SERIES], min: i32, max: i32) -> Self {
+        Self::new(colours)
+            .with_scale(Scale::Fixed { min, max })
+            .with_mode(Mode::Sweep)
+    }
+
+    pub fn with_scale(self, scale: Scale) -> Self {
+        Self { scale, ..self }
+    }
+
+    pub fn with_mode(self, mode: Mode) -> Self {
+        Self { mode, ..self }
+    }
+
+    /// Number of horizontal grid divisions, no grid if zero
+    pub fn with_grid(self, grid: u8) -> Self {
+        Self { grid, ..self }
+    }
+
+    /// Add a sample to each series
+    pub fn push(&mut self, samples: [i32; SERIES]) {
+        for (series, sample) in self.series.iter_mut().zip(samples.iter()) {
+            series.push(*sample);
+        }
+        self.count += 1;
+        self.state.dirty = true;
+    }
+
+    /// The samples of a series
+    pub fn series(&self, series: usize) -> Option<&RingBuffer<i32, N>> {
+        self.series.get(series)
+    }
+
+    /// Remove all samples
+    pub fn clear(&mut self) {
+        for series in self.series.iter_mut() {
+            series.clear();
+        }
+        self.count = 0;
+        self.state.dirty = true;
+    }
+
+    /// Range of the Y axis, never empty
+    fn range(&self) -> (i32, i32) {
+        let (min, max) = match self.scale {
+            Scale::Fixed { min, max } => (min, max),
+            Scale::Auto => self
+                .series
+                .iter()
+                .flat_map(|series| series.iter())
+                .fold(None, |range, sample| match range {
+                    None => Some((sample, sample)),
+                    Some((min, max)) => {
+                        Some((core::cmp::min(min, sample), core::cmp::max(max, sample)))
+                    }
+                })
+                .unwrap_or((0, 0)),
+        };
+        if max > min {
+            (min, max)
+        } else {
+            (min, min + 1)
+        }
+    }
+
+    /// Position of each sample of a series
+    fn positions(&self, series: usize, bounds: Rectangle) -> [Option<i16>; N] {
+        let mut positions = [None; N];
+        let samples = &self.series[series];
+        let (min, max) = self.range();
+        let height = bounds.size.height.saturating_sub(1) as i64;
+        let bottom = bounds.top_left.y as i64 + height;
+        for (index, sample) in samples.iter().enumerate() {
+            let slot = match self.mode {
+                Mode::Scroll => N - samples.len() + index,
+                Mode::Sweep => (self.count - samples.len() + index) % N,
+            };
+            let sample = (sample as i64).clamp(min as i64, max as i64);
+            let y = bottom - (sample - min as i64) * height / (max as i64 - min as i64);
+            positions[slot] = Some(y as i16);
+        }
+        if self.mode == Mode::Sweep && N > 1 {
+            // Gap after the cursor
+            positions[self.count % N] = None;
+        }
+        positions
+    }
+
+    fn x(&self, slot: usize, bounds: Rectangle) -> i32 {
+        let width = bounds.size.width.saturating_sub(1) as usize;
+        let offset = if N > 1 { slot * width / (N - 1) } else { 0 };
+        bounds.top_left.x + offset as i32
+    }
+
+    /// The first and last column of the samples at `positions`
+    fn span(&self, positions: &[Option<i16>; N], bounds: Rectangle) -> Option<(i32, i32)> {
+        let mut slots = (0..N).filter(|&slot| positions[slot].is_some());
+        let first = slots.next()?;
+        let last = slots.last().unwrap_or(first);
+        Some((self.x(first, bounds), self.x(last, bounds)))
+    }
+
+    /// Draw the line from each sample to the next, for the lines within
+    /// `columns`
+    fn draw_trace<D>(
+        &self,
+        target: &mut D,
+        bounds: Rectangle,
+        positions: &[Option<i16>; N],
+        colour: Palette8,
+        columns: (i32, i32),
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        for slot in 0..N {
+            let current = positions[slot];
+            let before = slot.checked_sub(1).and_then(|slot| positions[slot]);
+            let end = match current {
+                Some(y) => Point::new(self.x(slot, bounds), y as i32),
+                None => continue,
+            };
+            let start_x = match before {
+                Some(_) => self.x(slot - 1, bounds),
+                None => end.x,
+            };
+            if end.x < columns.0 || start_x > columns.1 {
+                continue;
+            }
+            match before {
+                Some(y) => {
+                    let start = Point::new(start_x, y as i32);
+                    Line::new(start, end)
+                        .into_styled(PrimitiveStyle::with_stroke(colour, 1))
+                        .draw(target)?;
+                }
+                None => target.draw_iter(core::iter::once(Pixel(end, colour)))?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Dotted horizontal grid lines within `columns`
+    fn draw_grid<D>(
+        &self,
+        target: &mut D,
+        bounds: Rectangle,
+        colour: Palette8,
+        columns: (i32, i32),
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        let height = bounds.size.height.saturating_sub(1) as i32;
+        for division in 0..=self.grid as i32 {
+            let y = bounds.top_left.y + division * height / core::cmp::max(self.grid as i32, 1);
+            let pixels = (0..bounds.size.width as i32)
+                .step_by(4)
+                .map(|x| bounds.top_left.x + x)
+                .filter(|x| (columns.0..=columns.1).contains(x))
+                .map(|x| Pixel(Point::new(x, y), colour));
+            target.draw_iter(pixels)?;
+            if self.grid == 0 {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Chart<1, N> {
+    /// Single series scaled to its samples without grid
+    pub fn sparkline(colour: Palette8) -> Self {
+        Self::new([colour]).with_grid(0)
+    }
+
+    /// Add a sample to the single series
+    pub fn push_sample(&mut self, sample: i32) {
+        self.push([sample]);
+    }
+}
+
+impl<D, const SERIES: usize, const N: usize> Widget<D> for Chart<SERIES, N>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn state(&self) -> &State {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    fn preferred_size(&self, _theme: &Theme) -> Size {
+        Size::zero()
+    }
+
+    fn draw_widget(&self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = self.state.bounds;
+        target.fill_solid(&bounds, theme.background)?;
+        let columns = all_columns(bounds);
+        if self.grid > 0 {
+            self.draw_grid(target, bounds, theme.foreground, columns)?;
+        }
+        for series in 0..SERIES {
+            let positions = self.positions(series, bounds);
+            self.draw_trace(target, bounds, &positions, self.colours[series], columns)?;
+        }
+        Ok(())
+    }
+
+    /// Draw the segments that moved since the last draw
+    ///
+    /// Only the columns of the moved segments are redrawn, clipped to those
+    /// columns so that overlapping series keep their order. In scroll mode
+    /// these are usually all columns.
+    fn draw(&mut self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = self.state.bounds;
+        let mut positions = [[None; N]; SERIES];
+        for series in 0..SERIES {
+            positions[series] = self.positions(series, bounds);
+        }
+        if bounds != self.drawn_bounds {
+            self.draw_widget(target, theme)?;
+        } else {
+            let mut columns: Option<(i32, i32)> = None;
+            for series in 0..SERIES {
+                // Erase the moved segments at their previous positions
+                let erased = previous_changes(&self.drawn[series], &positions[series]);
+                let drawn = previous_changes(&positions[series], &self.drawn[series]);
+                self.draw_trace(
+                    target,
+                    bounds,
+                    &erased,
+                    theme.background,
+                    all_columns(bounds),
+                )?;
+                for span in [self.span(&erased, bounds), self.span(&drawn, bounds)]
+                    .iter()
+                    .flatten()
+                {
+                    columns = Some(match columns {
+                        Some((first, last)) => {
+                            (core::cmp::min(first, span.0), core::cmp::max(last, span.1))
+                        }
+                        None => *span,
+                    });
+                }
+            }
+            if let Some(columns) = columns {
+                let area = Rectangle::with_corners(
+                    Point::new(columns.0, bounds.top_left.y),
+                    Point::new(columns.1, bounds.top_left.y + bounds.size.height as i32 - 1),
+                );
+                let mut target = target.clipped(&area);
+                if self.grid > 0 {
+                    self.draw_grid(&mut target, bounds, theme.foreground, columns)?;
+                }
+                for series in 0..SERIES {
+                    // Segments of other series may have been erased
+                    self.draw_trace(
+                        &mut target,
+                        bounds,
+                        &positions[series],
+                        self.colours[series],
+                        columns,
+                    )?;
+                }
+            }
+        }
+        self.drawn = positions;
+        self.drawn_bounds = bounds;
+        self.state.dirty = false;
+        Ok(())
+    }
+}
+
+/// The first and last column of `bounds`
+fn all_columns(bounds: Rectangle) -> (i32, i32) {
+    (
+        bounds.top_left.x,
+        bounds.top_left.x + bounds.size.width as i32 - 1,
+    )
+}
+
+/// The previous positions of the segments that moved, other positions are
+/// `None`
+fn previous_changes<const N: usize>(
+    previous: &[Option<i16>; N],
+    current: &[Option<i16>; N],
+) -> [Option<i16>; N] {
+    let mut changed = [None; N];
+    for slot in 0..N {
+        let moved = |slot: usize| previous[slot] != current[slot];
+        if moved(slot) || (slot + 1 < N && moved(slot + 1)) || (slot > 0 && moved(slot - 1)) {
+            changed[slot] = previous[slot];
+        }
+    }
+    changed
+}
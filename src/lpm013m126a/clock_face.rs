@@ -0,0 +1,618 @@
+//! # Clock faces
+//!
+//! [`AnalogClock`] draws a dial with hour, minute and second hands and the
+//! day of the month. [`DigitalClock`] draws the time in large seven segment
+//! digits above the date. Both are [`Widget`]s showing the
+//! [`DateTime`] set with `set_time()`, such as the time of a
+//! [`Clock`](crate::Clock).
+//!
+//! Hands and segments are thick lines with round caps, placed to a sixteenth
+//! of a pixel. A pixel is drawn when its centre is within the line, so
+//! slanted hands keep an even width without the steps of a one pixel line
+//! drawn several times.
+//!
+//! Drawing is incremental. The analog clock redraws the boxes around the
+//! hands that moved and the digital clock the digits that changed. Drawn to
+//! a [`FrameBuffer`](crate::lpm013m126a::FrameBuffer), only the lines of
+//! those boxes are sent. The boxes of the second hand are up to the radius
+//! of the dial high, more lines than the digits of the digital clock.
+
+use crate::{
+    lpm013m126a::{
+        widget::{FormatBuffer, State, Theme, Widget},
+        Alignment, Palette8, TextBox, TextStyle,
+    },
+    time::DateTime,
+};
+use core::fmt::Write;
+use embedded_graphics::{
+    draw_target::{DrawTarget, DrawTargetExt},
+    geometry::{Point, Size},
+    primitives::{PointsIter, Primitive, PrimitiveStyle, Rectangle},
+    Drawable, Pixel,
+};
+
+// Geometry is in sixteenths of a pixel
+const SUBPIXEL: i32 = 16;
+// Angles are in 720ths of a turn, clockwise from twelve o'clock
+const TURN: i32 = 720;
+// Scale of `sine()`
+const UNIT: i32 = 4096;
+
+/// Thick line with round caps
+#[derive(Clone, Copy, PartialEq)]
+struct Stroke {
+    start: Point,
+    end: Point,
+    width: i32,
+}
+
+impl Stroke {
+    fn new(start: Point, end: Point, width: i32) -> Self {
+        Self {
+            start,
+            end,
+            width: width.max(SUBPIXEL),
+        }
+    }
+
+    /// The pixels touched by the stroke
+    fn bounding_box(&self) -> Rectangle {
+        let reach = self.width / 2 + SUBPIXEL;
+        let pixel = |value: i32| value.div_euclid(SUBPIXEL);
+        Rectangle::with_corners(
+            Point::new(
+                pixel(self.start.x.min(self.end.x) - reach),
+                pixel(self.start.y.min(self.end.y) - reach),
+            ),
+            Point::new(
+                pixel(self.start.x.max(self.end.x) + reach),
+                pixel(self.start.y.max(self.end.y) + reach),
+            ),
+        )
+    }
+
+    /// Draw the pixels of the stroke within `clip`
+    fn draw<D>(&self, target: &mut D, clip: &Rectangle, colour: Palette8) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        let area = self.bounding_box().intersection(clip);
+        target.draw_iter(
+            area.points()
+                .filter(|point| self.covers(*point))
+                .map(|point| Pixel(point, colour)),
+        )
+    }
+
+    /// Is the centre of the pixel within the stroke?
+    fn covers(&self, point: Point) -> bool {
+        let centre = point * SUBPIXEL + Point::new(SUBPIXEL / 2, SUBPIXEL / 2);
+        let radius = (self.width / 2) as u64;
+        self.distance_squared(centre) <= radius * radius
+    }
+
+    /// Squared distance from the line segment
+    fn distance_squared(&self, point: Point) -> u64 {
+        let (ax, ay) = (
+            (self.end.x - self.start.x) as i64,
+            (self.end.y - self.start.y) as i64,
+        );
+        let (px, py) = (
+            (point.x - self.start.x) as i64,
+            (point.y - self.start.y) as i64,
+        );
+        let length = ax * ax + ay * ay;
+        let (dx, dy) = if length == 0 {
+            (px, py)
+        } else {
+            let along = (px * ax + py * ay).clamp(0, length);
+            (px - ax * along / length, py - ay * along / length)
+        };
+        (dx * dx + dy * dy) as u64
+    }
+}
+
+/// Sine of `angle` scaled by `UNIT`, using Bhaskara's approximation
+fn sine(angle: i32) -> i32 {
+    let half = TURN / 2;
+    let angle = angle.rem_euclid(TURN);
+    let (angle, sign) = if angle < half {
+        (angle, 1)
+    } else {
+        (angle - half, -1)
+    };
+    let product = angle * (half - angle);
+    sign * 4 * product * UNIT / (5 * half * half / 4 - product)
+}
+
+/// Point at `length` from `centre` in the direction of `angle`
+fn polar(centre: Point, angle: i32, length: i32) -> Point {
+    let x = sine(angle) * length / UNIT;
+    let y = sine(angle + TURN / 4) * length / UNIT;
+    centre + Point::new(x, -y)
+}
+
+/// Draw the text centred in `area`
+fn centred_text<D>(
+    target: &mut D,
+    area: Rectangle,
+    text: &str,
+    theme: &Theme,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    let height = theme.font.line_height();
+    let offset = area.size.height.saturating_sub(height) / 2;
+    let line = Rectangle::new(
+        area.top_left + Point::new(0, offset as i32),
+        Size::new(area.size.width, height),
+    );
+    let style = TextStyle::new(theme.font, theme.foreground).with_alignment(Alignment::Centre);
+    TextBox::new(text, line, style).draw(target).map(|_| ())
+}
+
+/// Clock with hands
+pub struct AnalogClock {
+    state: State,
+    time: DateTime,
+    seconds: bool,
+    date: bool,
+    // hour, minute and second hands as last drawn
+    drawn_hands: [Option<Stroke>; 3],
+    drawn_day: u8,
+    drawn_bounds: Rectangle,
+    drawn_theme: Option<Theme>,
+}
+
+impl AnalogClock {
+    /// Clock with a second hand and the day of the month
+    pub fn new(time: DateTime) -> Self {
+        Self {
+            state: State::default(),
+            time,
+            seconds: true,
+            date: true,
+            drawn_hands: [None; 3],
+            drawn_day: 0,
+            drawn_bounds: Rectangle::zero(),
+            drawn_theme: None,
+        }
+    }
+
+    /// Show the second hand, without the clock only changes every minute
+    pub fn with_seconds(mut self, seconds: bool) -> Self {
+        self.seconds = seconds;
+        self
+    }
+
+    /// Show the day of the month at three o'clock
+    pub fn with_date(mut self, date: bool) -> Self {
+        self.date = date;
+        self
+    }
+
+    pub fn time(&self) -> DateTime {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: DateTime) {
+        let old = self.time;
+        if (old.hour, old.minute) != (time.hour, time.minute)
+            || (self.seconds && old.second != time.second)
+            || (self.date && old.day != time.day)
+        {
+            self.state.dirty = true;
+        }
+        self.time = time;
+    }
+
+    /// Centre and radius of the dial
+    fn dial(bounds: Rectangle) -> (Point, i32) {
+        let size = bounds.size;
+        let centre = bounds.top_left * SUBPIXEL
+            + Point::new(size.width as i32, size.height as i32) * (SUBPIXEL / 2);
+        let radius = size.width.min(size.height) as i32 * SUBPIXEL / 2 - SUBPIXEL;
+        (centre, radius.max(0))
+    }
+
+    fn hands(&self, bounds: Rectangle) -> [Option<Stroke>; 3] {
+        let (centre, radius) = Self::dial(bounds);
+        let time = self.time;
+        let hand = |angle: i32, length: i32, tail: i32, width: i32| {
+            Stroke::new(
+                polar(centre, angle + TURN / 2, radius * tail / 100),
+                polar(centre, angle, radius * length / 100),
+                radius / width,
+            )
+        };
+        let hour = (time.hour % 12) as i32 * 60 + time.minute as i32;
+        let minute = time.minute as i32 * 12;
+        let second = time.second as i32 * 12;
+        [
+            Some(hand(hour, 50, 8, 16)),
+            Some(hand(minute, 78, 8, 24)),
+            if self.seconds {
+                Some(hand(second, 86, 18, 64))
+            } else {
+                None
+            },
+        ]
+    }
+
+    /// Box of the day of the month
+    fn date_box(bounds: Rectangle, theme: &Theme) -> Rectangle {
+        let (centre, radius) = Self::dial(bounds);
+        let size = theme.font.measure("00") + Size::new(5, 3);
+        Rectangle::new(
+            Point::new(
+                (centre.x + radius * 56 / 100) / SUBPIXEL,
+                centre.y / SUBPIXEL - size.height as i32 / 2,
+            ),
+            size,
+        )
+    }
+
+    /// Redraw everything within `clip`
+    fn draw_area<D>(
+        &self,
+        target: &mut D,
+        clip: Rectangle,
+        theme: &Theme,
+        hands: &[Option<Stroke>; 3],
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        let bounds = self.state.bounds;
+        let clip = clip.intersection(&bounds);
+        target.fill_solid(&clip, theme.background)?;
+        let (centre, radius) = Self::dial(bounds);
+        for tick in 0..60 {
+            let angle = tick * TURN / 60;
+            let (inner, width) = if tick % 5 == 0 { (84, 28) } else { (90, 88) };
+            Stroke::new(
+                polar(centre, angle, radius * inner / 100),
+                polar(centre, angle, radius * 94 / 100),
+                radius / width,
+            )
+            .draw(target, &clip, theme.foreground)?;
+        }
+        if self.date {
+            let area = Self::date_box(bounds, theme);
+            let mut clipped = target.clipped(&clip);
+            area.into_styled(PrimitiveStyle::with_stroke(theme.foreground, 1))
+                .draw(&mut clipped)?;
+            let mut day = FormatBuffer::<2>::new();
+            let _ = write!(day, "{}", self.time.day);
+            centred_text(&mut clipped, area, day.as_str(), theme)?;
+        }
+        let colours = [theme.foreground, theme.foreground, theme.accent];
+        for (hand, colour) in hands.iter().zip(colours.iter()) {
+            if let Some(hand) = hand {
+                hand.draw(target, &clip, *colour)?;
+            }
+        }
+        Stroke::new(centre, centre, radius / 10).draw(target, &clip, theme.accent)
+    }
+}
+
+impl<D> Widget<D> for AnalogClock
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn state(&self) -> &State {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    fn preferred_size(&self, _theme: &Theme) -> Size {
+        Size::zero()
+    }
+
+    /// Draw the whole clock on the next draw
+    fn mark_dirty(&mut self) {
+        self.drawn_theme = None;
+        self.state.dirty = true;
+    }
+
+    fn draw_widget(&self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = self.state.bounds;
+        self.draw_area(target, bounds, theme, &self.hands(bounds))
+    }
+
+    /// Redraw around the hands that moved
+    fn draw(&mut self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = self.state.bounds;
+        let hands = self.hands(bounds);
+        if bounds != self.drawn_bounds || self.drawn_theme != Some(*theme) {
+            self.draw_area(target, bounds, theme, &hands)?;
+        } else {
+            for (drawn, hand) in self.drawn_hands.iter().zip(hands.iter()) {
+                if drawn == hand {
+                    continue;
+                }
+                for stroke in drawn.iter().chain(hand.iter()) {
+                    self.draw_area(target, stroke.bounding_box(), theme, &hands)?;
+                }
+            }
+            if self.date && self.drawn_day != self.time.day {
+                self.draw_area(target, Self::date_box(bounds, theme), theme, &hands)?;
+            }
+        }
+        self.drawn_hands = hands;
+        self.drawn_day = self.time.day;
+        self.drawn_bounds = bounds;
+        self.drawn_theme = Some(*theme);
+        self.state.dirty = false;
+        Ok(())
+    }
+}
+
+// Segments a to g of the digits 0 to 9, a in bit 0
+const SEGMENTS: [u8; 10] = [0x3f, 0x06, 0x5b, 0x4f, 0x66, 0x6d, 0x7d, 0x07, 0x7f, 0x6f];
+
+/// Clock with seven segment digits
+pub struct DigitalClock {
+    state: State,
+    time: DateTime,
+    seconds: bool,
+    date: bool,
+    // hour, minute and second digits as last drawn
+    drawn_digits: [Option<u8>; 6],
+    drawn_date: Option<DateTime>,
+    drawn_bounds: Rectangle,
+    drawn_theme: Option<Theme>,
+}
+
+/// Areas of the digital clock
+struct Layout {
+    digits: [Rectangle; 6],
+    colon: Rectangle,
+    date: Rectangle,
+}
+
+impl DigitalClock {
+    /// Clock showing hours and minutes above the date
+    pub fn new(time: DateTime) -> Self {
+        Self {
+            state: State::default(),
+            time,
+            seconds: false,
+            date: true,
+            drawn_digits: [None; 6],
+            drawn_date: None,
+            drawn_bounds: Rectangle::zero(),
+            drawn_theme: None,
+        }
+    }
+
+    /// Show the seconds in smaller digits
+    pub fn with_seconds(mut self, seconds: bool) -> Self {
+        self.seconds = seconds;
+        self
+    }
+
+    /// Show the date below the time
+    pub fn with_date(mut self, date: bool) -> Self {
+        self.date = date;
+        self
+    }
+
+    pub fn time(&self) -> DateTime {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: DateTime) {
+        let old = self.time;
+        if (old.hour, old.minute) != (time.hour, time.minute)
+            || (self.seconds && old.second != time.second)
+            || (self.date && !old.same_date(&time))
+        {
+            self.state.dirty = true;
+        }
+        self.time = time;
+    }
+
+    fn digits(&self) -> [Option<u8>; 6] {
+        let time = self.time;
+        let seconds = |digit: u8| if self.seconds { Some(digit) } else { None };
+        [
+            Some(time.hour / 10),
+            Some(time.hour % 10),
+            Some(time.minute / 10),
+            Some(time.minute % 10),
+            seconds(time.second / 10),
+            seconds(time.second % 10),
+        ]
+    }
+
+    /// The areas, `None` if the bounds are too small for the digits
+    fn layout(&self, theme: &Theme) -> Option<Layout> {
+        let bounds = self.state.bounds;
+        let date_height = if self.date {
+            theme.font.line_height() + 4
+        } else {
+            0
+        };
+        let height = bounds.size.height.saturating_sub(date_height);
+        // Widths in quarters of a digit, the seconds are half size
+        let quarters = if self.seconds { 23 } else { 18 };
+        let width = (bounds.size.width * 4 / quarters).min(height / 2);
+        if width == 0 {
+            return None;
+        }
+        let height = width * 2;
+        let total = width * quarters / 4;
+        let left = bounds.top_left.x + (bounds.size.width.saturating_sub(total) / 2) as i32;
+        let top = bounds.top_left.y
+            + (bounds.size.height.saturating_sub(height + date_height) / 2) as i32;
+        let cell = |quarter: u32, size: u32| {
+            Rectangle::new(
+                Point::new(
+                    left + (quarter * width / 4) as i32,
+                    top + (height - size) as i32,
+                ),
+                Size::new(width * size / height, size),
+            )
+        };
+        Some(Layout {
+            digits: [
+                cell(0, height),
+                cell(4, height),
+                cell(10, height),
+                cell(14, height),
+                cell(19, height / 2),
+                cell(21, height / 2),
+            ],
+            colon: Rectangle::new(
+                Point::new(left + (8 * width / 4) as i32, top),
+                Size::new(width / 2, height),
+            ),
+            date: Rectangle::new(
+                Point::new(bounds.top_left.x, top + height as i32),
+                Size::new(bounds.size.width, date_height),
+            ),
+        })
+    }
+
+    fn draw_digit<D>(
+        &self,
+        target: &mut D,
+        area: Rectangle,
+        digit: u8,
+        theme: &Theme,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        target.fill_solid(&area, theme.background)?;
+        let width = area.size.width as i32 * SUBPIXEL / 7;
+        let inset = width / 2 + SUBPIXEL;
+        let top_left = area.top_left * SUBPIXEL;
+        let size = Point::new(area.size.width as i32, area.size.height as i32) * SUBPIXEL;
+        let (left, right) = (top_left.x + inset, top_left.x + size.x - inset);
+        let (top, bottom) = (top_left.y + inset, top_left.y + size.y - inset);
+        let middle = top_left.y + size.y / 2;
+        let corners = [
+            (Point::new(left, top), Point::new(right, top)),
+            (Point::new(right, top), Point::new(right, middle)),
+            (Point::new(right, middle), Point::new(right, bottom)),
+            (Point::new(left, bottom), Point::new(right, bottom)),
+            (Point::new(left, middle), Point::new(left, bottom)),
+            (Point::new(left, top), Point::new(left, middle)),
+            (Point::new(left, middle), Point::new(right, middle)),
+        ];
+        // Gap between the ends of the segments
+        let gap = width * 3 / 4;
+        let segments = SEGMENTS[digit as usize % 10];
+        for (segment, (start, end)) in corners.iter().enumerate() {
+            if segments & (1 << segment) == 0 {
+                continue;
+            }
+            let step = Point::new((end.x - start.x).signum(), (end.y - start.y).signum()) * gap;
+            Stroke::new(*start + step, *end - step, width).draw(target, &area, theme.foreground)?;
+        }
+        Ok(())
+    }
+
+    fn draw_date<D>(&self, target: &mut D, area: Rectangle, theme: &Theme) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        target.fill_solid(&area, theme.background)?;
+        let time = self.time;
+        let mut text = FormatBuffer::<16>::new();
+        let _ = write!(
+            text,
+            "{} {} {} {}",
+            time.weekday_name(),
+            time.day,
+            time.month_name(),
+            time.year
+        );
+        centred_text(target, area, text.as_str(), theme)
+    }
+}
+
+impl<D> Widget<D> for DigitalClock
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn state(&self) -> &State {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    fn preferred_size(&self, _theme: &Theme) -> Size {
+        Size::zero()
+    }
+
+    /// Draw the whole clock on the next draw
+    fn mark_dirty(&mut self) {
+        self.drawn_theme = None;
+        self.state.dirty = true;
+    }
+
+    fn draw_widget(&self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        target.fill_solid(&self.state.bounds, theme.background)?;
+        let layout = match self.layout(theme) {
+            Some(layout) => layout,
+            None => return Ok(()),
+        };
+        for (digit, area) in self.digits().iter().zip(layout.digits.iter()) {
+            if let Some(digit) = digit {
+                self.draw_digit(target, *area, *digit, theme)?;
+            }
+        }
+        let colon = layout.colon;
+        let width = colon.size.width as i32 * SUBPIXEL * 2 / 7;
+        let x = colon.top_left.x * SUBPIXEL + colon.size.width as i32 * SUBPIXEL / 2;
+        for third in 1..3 {
+            let y = colon.top_left.y * SUBPIXEL + colon.size.height as i32 * SUBPIXEL * third / 3;
+            let dot = Point::new(x, y);
+            Stroke::new(dot, dot, width).draw(target, &colon, theme.accent)?;
+        }
+        if self.date {
+            self.draw_date(target, layout.date, theme)?;
+        }
+        Ok(())
+    }
+
+    /// Redraw the digits that changed
+    fn draw(&mut self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = self.state.bounds;
+        let digits = self.digits();
+        if bounds != self.drawn_bounds || self.drawn_theme != Some(*theme) {
+            self.draw_widget(target, theme)?;
+        } else if let Some(layout) = self.layout(theme) {
+            for (index, digit) in digits.iter().enumerate() {
+                match *digit {
+                    Some(digit) if self.drawn_digits[index] != Some(digit) => {
+                        self.draw_digit(target, layout.digits[index], digit, theme)?
+                    }
+                    _ => (),
+                }
+            }
+            let date_changed = self
+                .drawn_date
+                .map_or(true, |date| !date.same_date(&self.time));
+            if self.date && date_changed {
+                self.draw_date(target, layout.date, theme)?;
+            }
+        }
+        self.drawn_digits = digits;
+        self.drawn_date = Some(self.time);
+        self.drawn_bounds = bounds;
+        self.drawn_theme = Some(*theme);
+        self.state.dirty = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+//! # Commands of the memory in pixel displays
+
+/// SPI commands for the JDI LPM013M126A
+/// The command layout is
+#[derive(Clone, Copy, PartialEq)]
+pub enum Command {
+    /// Update the specified line in 3-bit colour mode
+    DrawLines3bit,
+    /// Update the specified line in 1-bit colour mode
+    DrawLines1bit,
+    /// Update the specified line in 4-bit colour mode
+    DrawLines4bit,
+    /// No update
+    NoUpdate,
+    /// Clear screen
+    Clear,
+    /// Blink off
+    BlinkOff,
+    /// Blink black
+    BlinkBlack,
+    /// Blink white
+    BlinkWhite,
+    /// Inverted colours
+    InvertOn,
+    /// Normal colours
+    InvertOff,
+}
+
+impl From<Command> for u8 {
+    fn from(value: Command) -> u8 {
+        use Command::*;
+        match value {
+            DrawLines3bit => 0b_1000_0000,
+            DrawLines1bit => 0b_1000_1000,
+            DrawLines4bit => 0b_1001_0000,
+            NoUpdate => 0b_0000_0000,
+            Clear => 0b_0010_0000,
+            BlinkOff => 0b_0000_0000,
+            BlinkBlack => 0b_0001_0000,
+            BlinkWhite => 0b_0001_1000,
+            InvertOn => 0b_0001_0100,
+            InvertOff => 0b_0000_0000,
+        }
+    }
+}
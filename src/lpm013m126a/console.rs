@@ -0,0 +1,316 @@
+//! # Scrolling text console
+//!
+//! [`Console`] is a grid of `COLUMNS` by `ROWS` characters written through
+//! `core::fmt::Write`, so `write!` and `writeln!` can be used to show
+//! diagnostics on the display. When the last row is full the content scrolls
+//! up one row.
+//!
+//! Written characters are kept in the console and drawn with `draw()`, which
+//! only draws the rows changed since the previous call. Drawn to a
+//! [`FrameBuffer`](super::FrameBuffer), only the lines of those rows are
+//! sent.
+//!
+//! The control characters `\n`, `\r`, `\t` and backspace are handled, as
+//! well as a subset of the ANSI escape sequences.
+//!
+//! * `ESC[<n>m`, select graphic rendition. 0 resets the colours, 7 and 27
+//!   swaps and restores the colours, 30–37 and 90–97 sets the foreground,
+//!   40–47 and 100–107 sets the background, 39 and 49 resets the foreground
+//!   and background. The eight ANSI colours map to the eight colours of the
+//!   display, bright colours are the same as normal colours.
+//! * `ESC[2J`, clears the console.
+//! * `ESC[K`, clears to the end of the row.
+
+use crate::lpm013m126a::{Font, Palette8};
+use core::fmt;
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    mono_font::MonoTextStyle,
+    primitives::Rectangle,
+    text::{Baseline, Text},
+    Drawable,
+};
+
+const TAB_WIDTH: usize = 4;
+const MAX_PARAMETERS: usize = 4;
+
+#[derive(Clone, Copy, PartialEq)]
+struct Cell {
+    character: char,
+    foreground: Palette8,
+    background: Palette8,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Escape {
+    None,
+    // Received ESC
+    Escape,
+    // Received ESC [, collecting parameters
+    Csi,
+}
+
+/// Colour of an ANSI colour number
+fn ansi_colour(n: u16) -> Palette8 {
+    Palette8::from_rgb(n & 1 != 0, n & 2 != 0, n & 4 != 0)
+}
+
+/// Text console of `COLUMNS` by `ROWS` characters
+pub struct Console<const COLUMNS: usize, const ROWS: usize> {
+    cells: [[Cell; COLUMNS]; ROWS],
+    dirty: [bool; ROWS],
+    // index in `cells` of the top row
+    top: usize,
+    column: usize,
+    row: usize,
+    foreground: Palette8,
+    background: Palette8,
+    default_foreground: Palette8,
+    default_background: Palette8,
+    // foreground and background swapped
+    inverse: bool,
+    origin: Point,
+    font: Font,
+    escape: Escape,
+    parameters: [u16; MAX_PARAMETERS],
+    parameter_count: usize,
+}
+
+impl<const COLUMNS: usize, const ROWS: usize> Console<COLUMNS, ROWS> {
+    /// Empty console with its top left corner at `origin`
+    pub fn new(origin: Point, font: Font, foreground: Palette8, background: Palette8) -> Self {
+        let blank = Cell {
+            character: ' ',
+            foreground,
+            background,
+        };
+        Self {
+            cells: [[blank; COLUMNS]; ROWS],
+            dirty: [true; ROWS],
+            top: 0,
+            column: 0,
+            row: 0,
+            foreground,
+            background,
+            default_foreground: foreground,
+            default_background: background,
+            inverse: false,
+            origin,
+            font,
+            escape: Escape::None,
+            parameters: [0; MAX_PARAMETERS],
+            parameter_count: 0,
+        }
+    }
+
+    /// The area drawn by the console
+    pub fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(
+            self.origin,
+            Size::new(
+                COLUMNS as u32 * self.font.character_width(),
+                ROWS as u32 * self.font.line_height(),
+            ),
+        )
+    }
+
+    /// Set the colours of the following characters
+    pub fn set_colours(&mut self, foreground: Palette8, background: Palette8) {
+        self.foreground = foreground;
+        self.background = background;
+        self.inverse = false;
+    }
+
+    /// Clear all rows and move the cursor to the top left
+    pub fn clear(&mut self) {
+        for row in 0..ROWS {
+            self.clear_row(row, 0);
+        }
+        self.column = 0;
+        self.row = 0;
+    }
+
+    /// Are there rows changed since the last draw?
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.iter().any(|&dirty| dirty)
+    }
+
+    fn cell_mut(&mut self, row: usize, column: usize) -> &mut Cell {
+        &mut self.cells[(self.top + row) % ROWS][column]
+    }
+
+    /// Clear a row from `column` to the end
+    fn clear_row(&mut self, row: usize, column: usize) {
+        let blank = Cell {
+            character: ' ',
+            foreground: self.foreground,
+            background: self.background,
+        };
+        for column in column..COLUMNS {
+            *self.cell_mut(row, column) = blank;
+        }
+        self.dirty[row] = true;
+    }
+
+    fn new_line(&mut self) {
+        self.column = 0;
+        if self.row + 1 < ROWS {
+            self.row += 1;
+        } else {
+            // Scroll, the top row becomes the new bottom row
+            self.top = (self.top + 1) % ROWS;
+            self.clear_row(ROWS - 1, 0);
+            for dirty in self.dirty.iter_mut() {
+                *dirty = true;
+            }
+        }
+    }
+
+    fn put(&mut self, character: char) {
+        if COLUMNS == 0 || ROWS == 0 {
+            return;
+        }
+        match character {
+            '\n' => self.new_line(),
+            '\r' => self.column = 0,
+            '\t' => {
+                let next = (self.column / TAB_WIDTH + 1) * TAB_WIDTH;
+                while self.column < next && self.column < COLUMNS {
+                    self.put(' ');
+                }
+            }
+            '\x08' => self.column = self.column.saturating_sub(1),
+            c if c.is_control() => (),
+            c => {
+                if self.column >= COLUMNS {
+                    self.new_line();
+                }
+                let cell = Cell {
+                    character: c,
+                    foreground: self.foreground,
+                    background: self.background,
+                };
+                let (row, column) = (self.row, self.column);
+                *self.cell_mut(row, column) = cell;
+                self.dirty[row] = true;
+                self.column += 1;
+            }
+        }
+    }
+
+    /// Apply select graphic rendition parameters
+    fn select_graphic_rendition(&mut self) {
+        let count = self.parameter_count.clamp(1, MAX_PARAMETERS);
+        for i in 0..count {
+            match self.parameters[i] {
+                0 => {
+                    self.foreground = self.default_foreground;
+                    self.background = self.default_background;
+                    self.inverse = false;
+                }
+                7 if !self.inverse => {
+                    core::mem::swap(&mut self.foreground, &mut self.background);
+                    self.inverse = true;
+                }
+                27 if self.inverse => {
+                    core::mem::swap(&mut self.foreground, &mut self.background);
+                    self.inverse = false;
+                }
+                n @ 30..=37 => self.foreground = ansi_colour(n - 30),
+                n @ 90..=97 => self.foreground = ansi_colour(n - 90),
+                n @ 40..=47 => self.background = ansi_colour(n - 40),
+                n @ 100..=107 => self.background = ansi_colour(n - 100),
+                39 => self.foreground = self.default_foreground,
+                49 => self.background = self.default_background,
+                _ => (),
+            }
+        }
+    }
+
+    fn escape_sequence(&mut self, character: char) {
+        match (self.escape, character) {
+            (Escape::Escape, '[') => {
+                self.escape = Escape::Csi;
+                self.parameters = [0; MAX_PARAMETERS];
+                self.parameter_count = 0;
+            }
+            (Escape::Csi, '0'..='9') => {
+                if self.parameter_count == 0 {
+                    self.parameter_count = 1;
+                }
+                if let Some(parameter) = self.parameters.get_mut(self.parameter_count - 1) {
+                    let digit = character as u16 - '0' as u16;
+                    *parameter = parameter.saturating_mul(10).saturating_add(digit);
+                }
+            }
+            (Escape::Csi, ';') => {
+                self.parameter_count = core::cmp::max(self.parameter_count, 1) + 1;
+            }
+            (Escape::Csi, 'm') => {
+                self.select_graphic_rendition();
+                self.escape = Escape::None;
+            }
+            (Escape::Csi, 'J') => {
+                if self.parameters[0] == 2 {
+                    self.clear();
+                }
+                self.escape = Escape::None;
+            }
+            (Escape::Csi, 'K') => {
+                let (row, column) = (self.row, self.column);
+                self.clear_row(row, core::cmp::min(column, COLUMNS));
+                self.escape = Escape::None;
+            }
+            // Unsupported sequences are ignored
+            _ => self.escape = Escape::None,
+        }
+    }
+
+    /// Draw the rows changed since the last draw
+    pub fn draw<D>(&mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        let width = self.font.character_width() as i32;
+        let height = self.font.line_height() as i32;
+        for row in 0..ROWS {
+            if !self.dirty[row] {
+                continue;
+            }
+            let cells = &self.cells[(self.top + row) % ROWS];
+            for (column, cell) in cells.iter().enumerate() {
+                let position = self.origin + Point::new(column as i32 * width, row as i32 * height);
+                // Fill the character spacing as well
+                target.fill_solid(
+                    &Rectangle::new(position, Size::new(width as u32, height as u32)),
+                    cell.background,
+                )?;
+                let mut buffer = [0u8; 4];
+                let text = cell.character.encode_utf8(&mut buffer);
+                let style = MonoTextStyle::new(self.font.mono_font(), cell.foreground);
+                Text::with_baseline(text, position, style, Baseline::Top).draw(target)?;
+            }
+            self.dirty[row] = false;
+        }
+        Ok(())
+    }
+}
+
+impl<const COLUMNS: usize, const ROWS: usize> fmt::Write for Console<COLUMNS, ROWS> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.write_char(c)?;
+        }
+        Ok(())
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        match (self.escape, c) {
+            (Escape::None, '\x1b') => self.escape = Escape::Escape,
+            (Escape::None, c) => self.put(c),
+            (_, c) => self.escape_sequence(c),
+        }
+        Ok(())
+    }
+}
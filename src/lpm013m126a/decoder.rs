@@ -0,0 +1,179 @@
+//! # Decoder of the SPI byte stream sent to memory in pixel displays
+//!
+//! Reconstructs the image and mode of a [`Panel`] from the octets of each SPI
+//! transfer, the octets sent while chip select is asserted. This verifies the
+//! driver independently of its frame buffer and helps reading logic analyzer
+//! captures.
+//!
+//! The transfers recognized are those sent by the driver, a two octet
+//! command or a single line update of header, pixel data and trailer.
+
+use crate::lpm013m126a::{Command, FrameBuffer, Palette8, Panel};
+
+/// Blink state of the display
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Blink {
+    Off,
+    Black,
+    White,
+}
+
+/// A successfully decoded transfer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transfer {
+    /// The line was updated
+    Line(u16),
+    /// Command without data
+    Command(u8),
+}
+
+/// Malformed transfers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecodeError {
+    /// The transfer is too short to hold a command
+    Empty,
+    /// A command octet not known to the panel
+    UnknownCommand(u8),
+    /// A line header not known to the panel
+    UnknownHeader([u8; 2]),
+    /// The line address is outside the panel
+    LineOutOfRange(u16),
+    /// The transfer length does not match the command, the expected length
+    Length { expected: usize, actual: usize },
+    /// The trailing dummy octets are not zero
+    Trailer,
+}
+
+/// Decoder holding the state of the emulated panel
+pub struct Decoder<P: Panel> {
+    frame_buffer: FrameBuffer<P>,
+    blink: Blink,
+    inverted: bool,
+    lines: u32,
+    errors: u32,
+}
+
+impl<P> Default for Decoder<P>
+where
+    P: Panel,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> Decoder<P>
+where
+    P: Panel,
+{
+    pub fn new() -> Self {
+        Self {
+            frame_buffer: FrameBuffer::new(),
+            blink: Blink::Off,
+            inverted: false,
+            lines: 0,
+            errors: 0,
+        }
+    }
+
+    /// Decode a single transfer and apply it to the panel state
+    ///
+    /// A malformed transfer does not change the panel state.
+    pub fn decode(&mut self, transfer: &[u8]) -> Result<Transfer, DecodeError> {
+        let result = self.decode_transfer(transfer);
+        if result.is_err() {
+            self.errors += 1;
+        }
+        result
+    }
+
+    fn decode_transfer(&mut self, transfer: &[u8]) -> Result<Transfer, DecodeError> {
+        if transfer.len() < 2 {
+            return Err(DecodeError::Empty);
+        }
+        let header = [transfer[0], transfer[1]];
+        if let Some(line) = P::decode_line_header(header) {
+            if line >= P::HEIGHT {
+                return Err(DecodeError::LineOutOfRange(line));
+            }
+            if transfer.len() != P::LINE_COMMAND_OCTETS {
+                return Err(DecodeError::Length {
+                    expected: P::LINE_COMMAND_OCTETS,
+                    actual: transfer.len(),
+                });
+            }
+            let (data, trailer) = transfer[2..].split_at(P::LINE_OCTETS);
+            if trailer.iter().any(|&octet| octet != 0) {
+                return Err(DecodeError::Trailer);
+            }
+            self.frame_buffer.line_mut(line).copy_from_slice(data);
+            self.lines += 1;
+            return Ok(Transfer::Line(line));
+        }
+        let command = P::decode_command(header[0]);
+        if command.is_none() {
+            if header[0] & 0b_1000_0000 != 0 {
+                return Err(DecodeError::UnknownHeader(header));
+            }
+            return Err(DecodeError::UnknownCommand(header[0]));
+        }
+        if transfer.len() != 2 {
+            return Err(DecodeError::Length {
+                expected: 2,
+                actual: transfer.len(),
+            });
+        }
+        if header[1] != 0 {
+            return Err(DecodeError::Trailer);
+        }
+        match command {
+            Some(Command::Clear) => self.frame_buffer.fill(Palette8::White),
+            Some(Command::BlinkBlack) => self.blink = Blink::Black,
+            Some(Command::BlinkWhite) => self.blink = Blink::White,
+            Some(Command::InvertOn) => self.inverted = true,
+            _ => {
+                self.blink = Blink::Off;
+                self.inverted = false;
+            }
+        }
+        Ok(Transfer::Command(header[0]))
+    }
+
+    /// The pixel memory of the panel
+    pub fn frame_buffer(&self) -> &FrameBuffer<P> {
+        &self.frame_buffer
+    }
+
+    pub fn blink(&self) -> Blink {
+        self.blink
+    }
+
+    pub fn inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Number of line updates decoded
+    pub fn lines(&self) -> u32 {
+        self.lines
+    }
+
+    /// Number of malformed transfers
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// The colour shown at a pixel in display coordinates, with blink and
+    /// inversion applied
+    pub fn visible_pixel(&self, x: u16, y: u16) -> Palette8 {
+        let colour = match self.blink {
+            Blink::Off => self.frame_buffer.read_pixel(x, y),
+            Blink::Black => Palette8::Black,
+            Blink::White => Palette8::White,
+        };
+        if self.inverted {
+            colour.inverted()
+        } else {
+            colour
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! # Dithering of RGB colours to the eight colours of the display
+//!
+//! [`Dither`] wraps a `Palette8` draw target and accepts `Rgb888` pixels.
+//! Other RGB colour types can be drawn using `color_converted()`.
+//!
+//! Ordered dithering works for any drawing operation. Floyd–Steinberg error
+//! diffusion needs the pixels in line order and is only applied to
+//! `fill_contiguous`, which is used when drawing images. Other operations fall
+//! back to ordered dithering.
+
+use crate::lpm013m126a::{Palette8, DISPLAY_WIDTH};
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, Point, Size},
+    pixelcolor::{Rgb888, RgbColor},
+    primitives::{PointsIter, Rectangle},
+    Pixel,
+};
+
+pub use microbit_two_convert::Dithering;
+use microbit_two_convert::ErrorDiffusion;
+
+// Widest line that can be error diffused
+const MAX_LINE: usize = DISPLAY_WIDTH as usize;
+
+/// Draw target adapter dithering `Rgb888` into `Palette8`
+pub struct Dither<'a, D> {
+    target: &'a mut D,
+    method: Dithering,
+}
+
+impl<'a, D> Dither<'a, D>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    pub fn new(target: &'a mut D, method: Dithering) -> Self {
+        Self { target, method }
+    }
+
+    pub fn set_method(&mut self, method: Dithering) {
+        self.method = method;
+    }
+
+    fn floyd_steinberg<I>(&mut self, area: &Rectangle, colours: I) -> Result<(), D::Error>
+    where
+        I: IntoIterator<Item = Rgb888>,
+    {
+        let width = area.size.width as usize;
+        let mut diffusion =
+            ErrorDiffusion::new([[0i16; 3]; MAX_LINE + 2], [[0i16; 3]; MAX_LINE + 2]);
+        let mut line = [Palette8::Black; MAX_LINE];
+        let mut colours = colours.into_iter();
+
+        for y in area.rows() {
+            let mut count = 0;
+            for x in 0..width {
+                let colour = match colours.next() {
+                    Some(c) => c,
+                    None => break,
+                };
+                line[x] = Palette8::from(diffusion.push([colour.r(), colour.g(), colour.b()]));
+                count += 1;
+            }
+            let row = Rectangle::new(Point::new(area.top_left.x, y), Size::new(count as u32, 1));
+            self.target
+                .fill_contiguous(&row, line[..count].iter().copied())?;
+            if count < width {
+                break;
+            }
+            diffusion.next_line();
+        }
+        Ok(())
+    }
+}
+
+impl<D> Dimensions for Dither<'_, D>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<D> DrawTarget for Dither<'_, D>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    type Color = Rgb888;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let method = self.method;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, colour)| Pixel(point, ordered(method, point, colour))),
+        )
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colours: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if self.method == Dithering::FloydSteinberg && area.size.width as usize <= MAX_LINE {
+            return self.floyd_steinberg(area, colours);
+        }
+        let method = self.method;
+        self.target.fill_contiguous(
+            area,
+            area.points()
+                .zip(colours)
+                .map(|(point, colour)| ordered(method, point, colour)),
+        )
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, colour: Self::Color) -> Result<(), Self::Error> {
+        let nearest = Palette8::from(colour);
+        if self.method == Dithering::Nearest || Rgb888::from(nearest) == colour {
+            return self.target.fill_solid(area, nearest);
+        }
+        // Error diffusion of a solid area is close to the ordered pattern
+        let method = Dithering::Bayer;
+        self.target.fill_contiguous(
+            area,
+            area.points().map(|point| ordered(method, point, colour)),
+        )
+    }
+}
+
+/// Ordered dither a single pixel
+fn ordered(method: Dithering, point: Point, colour: Rgb888) -> Palette8 {
+    let rgb = [colour.r(), colour.g(), colour.b()];
+    Palette8::from(microbit_two_convert::ordered(method, point.x, point.y, rgb))
+}
@@ -0,0 +1,287 @@
+//! # Double buffered memory in pixel display
+//!
+//! Drawing into the frame buffer while lines are being sent shows parts of
+//! two frames on the display. [`DoubleBuffered`] draws into a back buffer
+//! and hands it to the display when updating, while the previously sent
+//! frame buffer becomes the new back buffer.
+//!
+//! The frame buffers are exchanged, not copied, so caller provided
+//! `&'static mut` storage is preferred over owned storage. The new back
+//! buffer is brought up to date with the sent frame when it is next drawn
+//! to, outside of the SPI interrupt.
+
+use crate::{
+    lpm013m126a::{
+        screenshot, FrameBuffer, FrameStats, MipDisplay, Orientation, Palette8, Panel,
+        StaticStorage,
+    },
+    spim::{Instance, Spim},
+    Error,
+};
+use embedded_hal::digital::v2::OutputPin;
+
+#[cfg(feature = "graphics")]
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    primitives::Rectangle,
+    Pixel,
+};
+
+/// Memory in pixel display drawing into a back buffer
+pub struct DoubleBuffered<SPI: Instance, DISP, P: Panel, S = <P as Panel>::FrameBuffer> {
+    display: MipDisplay<SPI, DISP, P, S>,
+    back: FrameBuffer<P, S>,
+    pending: bool,
+    // the back buffer is to be copied from the front buffer before drawing
+    stale: bool,
+}
+
+impl<SPI, DISP, P> DoubleBuffered<SPI, DISP, P>
+where
+    SPI: Instance,
+    DISP: OutputPin,
+    P: Panel,
+{
+    pub fn new(spi: Spim<SPI>, display: DISP, line_buffer: &'static mut P::LineBuffer) -> Self {
+        Self {
+            display: MipDisplay::new(spi, display, line_buffer),
+            back: FrameBuffer::new(),
+            pending: false,
+            stale: false,
+        }
+    }
+}
+
+impl<SPI, DISP, P, S> DoubleBuffered<SPI, DISP, P, S>
+where
+    SPI: Instance,
+    DISP: OutputPin,
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Double buffered driver using caller provided frame buffer storage
+    ///
+    /// See [`FrameBuffer::with_storage`] for the supported storage sizes.
+    pub fn with_frame_buffers(
+        spi: Spim<SPI>,
+        display: DISP,
+        line_buffer: &'static mut P::LineBuffer,
+        front: S,
+        back: S,
+    ) -> Result<Self, Error>
+    where
+        S: StaticStorage,
+    {
+        Ok(Self {
+            display: MipDisplay::with_frame_buffer(spi, display, line_buffer, front)?,
+            back: FrameBuffer::with_storage(back)?,
+            pending: false,
+            stale: false,
+        })
+    }
+
+    /// Release the peripherals, the line buffer and both frame buffer
+    /// storages
+    pub fn release_frame_buffers(mut self) -> (Spim<SPI>, DISP, &'static mut P::LineBuffer, S, S) {
+        self.back_mut();
+        let (spi, display, line_buffer, front) = self.display.release_frame_buffer();
+        (spi, display, line_buffer, front, self.back.release())
+    }
+
+    pub fn init(&mut self) -> Result<(), Error> {
+        self.display.init()
+    }
+
+    /// Handle the SPI end of transfer event
+    ///
+    /// Returns `true` when a frame has been sent to the display. A pending
+    /// update is then started and the back buffer can be drawn again.
+    pub fn spi_task_event(&mut self) -> bool {
+        self.display.spi_task_event();
+        if self.display.is_drawing() {
+            return false;
+        }
+        if self.pending {
+            let _ = self.present();
+        }
+        true
+    }
+
+    /// Send the back buffer to the display
+    ///
+    /// If a frame is being sent, the update is pending until that frame has
+    /// been sent. Drawing before then is part of the pending update.
+    pub fn update_display(&mut self) -> Result<(), Error> {
+        // the frame presented must be complete, also when nothing was drawn
+        self.back_mut();
+        if self.display.is_drawing() {
+            // a second request before the first is presented is merged
+            if self.pending {
+                self.display.stats_mut().request(true);
+            }
+            self.pending = true;
+            Ok(())
+        } else {
+            self.present()
+        }
+    }
+
+    /// Is an update waiting for the current frame to be sent?
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Exchange the frame buffers and start sending the new front buffer
+    ///
+    /// Called from the SPI interrupt for pending updates, the back buffer is
+    /// copied from the front buffer later by `back_mut()`.
+    fn present(&mut self) -> Result<(), Error> {
+        self.pending = false;
+        self.display.swap_frame_buffer(&mut self.back);
+        self.stale = true;
+        self.display.update_display()
+    }
+
+    /// The back buffer, with the pixels of the front buffer once presented
+    fn back_mut(&mut self) -> &mut FrameBuffer<P, S> {
+        if self.stale {
+            let front = self.display.frame_buffer();
+            self.back.copy_from(front);
+            // the display shows the front buffer once sent
+            self.back.copy_changed_from(front);
+            self.stale = false;
+        }
+        &mut self.back
+    }
+
+    /// Clear the display memory, every line of both frame buffers is sent
+    /// with the next update
+    pub fn send_clear(&mut self) -> Result<(), Error> {
+        self.display.send_clear()?;
+        self.back.mark_all_changed();
+        Ok(())
+    }
+
+    /// Timing of the sent frames
+    pub fn stats(&self) -> &FrameStats {
+        self.display.stats()
+    }
+
+    /// Clock used to time frames, see [`FrameStats`]
+    pub fn set_clock(&mut self, clock: fn() -> u32, ticks_per_second: u32) {
+        self.display.set_clock(clock, ticks_per_second);
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.display.reset_stats();
+    }
+
+    /// Log at most `lines` lines of the front buffer, as shown on the
+    /// display, for the `screenshot` example
+    pub fn log_screenshot(&self, capture: &mut screenshot::Capture, lines: u16) -> bool {
+        self.display.log_screenshot(capture, lines)
+    }
+
+    /// The back buffer drawn to
+    pub fn frame_buffer(&self) -> &FrameBuffer<P, S> {
+        if self.stale {
+            // the pixels are the same once copied
+            self.display.frame_buffer()
+        } else {
+            &self.back
+        }
+    }
+
+    pub fn frame_buffer_mut(&mut self) -> &mut FrameBuffer<P, S> {
+        self.back_mut()
+    }
+
+    /// Set the orientation used when drawing
+    ///
+    /// Already drawn content is not moved.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.display.set_orientation(orientation);
+        self.back.set_orientation(orientation);
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.back.orientation()
+    }
+
+    /// Width of the display in the current orientation
+    pub fn width(&self) -> u16 {
+        self.back.width()
+    }
+
+    /// Height of the display in the current orientation
+    pub fn height(&self) -> u16 {
+        self.back.height()
+    }
+
+    /// Set a pixel, the coordinates are in the current orientation
+    pub fn set_pixel(&mut self, x: u16, y: u16, colour: Palette8) {
+        self.back_mut().set_pixel(x, y, colour);
+    }
+
+    /// Fill the whole back buffer with a single colour
+    pub fn fill(&mut self, colour: Palette8) {
+        // every pixel is replaced, there is nothing to copy
+        self.stale = false;
+        self.back.fill(colour);
+    }
+
+    /// Fill a rectangle with a single colour, the coordinates are in the
+    /// current orientation
+    ///
+    /// The rectangle is clipped to the display.
+    pub fn fill_rect(&mut self, x: u16, y: u16, width: u16, height: u16, colour: Palette8) {
+        self.back_mut().fill_rect(x, y, width, height, colour);
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<SPI, DISP, P, S> DrawTarget for DoubleBuffered<SPI, DISP, P, S>
+where
+    SPI: Instance,
+    DISP: OutputPin,
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+{
+    type Color = Palette8;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.back_mut().draw_iter(pixels)
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colours: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.back_mut().fill_contiguous(area, colours)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, colour: Self::Color) -> Result<(), Self::Error> {
+        self.back_mut().fill_solid(area, colour)
+    }
+
+    fn clear(&mut self, colour: Self::Color) -> Result<(), Self::Error> {
+        self.stale = false;
+        self.back.clear(colour)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<SPI, DISP, P, S> OriginDimensions for DoubleBuffered<SPI, DISP, P, S>
+where
+    SPI: Instance,
+    P: Panel,
+{
+    fn size(&self) -> Size {
+        self.back.size()
+    }
+}
@@ -0,0 +1,495 @@
+//! # Frame buffer for memory in pixel displays
+//!
+//! Holds the pixels of a [`Panel`] in the format sent to the display and
+//! implements the drawing operations. The frame buffer does not depend on
+//! any hardware and can be used on the host, see the `simulator` module.
+//!
+//! Lines drawn to are marked as changed until they are sent, the drivers
+//! send only the changed lines. A new frame buffer has every line changed.
+//!
+//! The storage is either owned, `P::FrameBuffer`, or provided by the caller,
+//! such as a `&'static mut` array placed in a specific RAM section. Caller
+//! provided storage selects the layout from its length.
+//!
+//! * `P::FRAME_OCTETS`, the pixel data of each line back to back.
+//! * `P::COMMAND_FRAME_OCTETS`, each line stored as a complete line update
+//!   command with header and trailer. The drivers send each line from the
+//!   storage without copying, which requires [`StaticStorage`].
+
+use crate::{
+    lpm013m126a::{Orientation, Palette8, Panel},
+    Error,
+};
+use core::marker::PhantomData;
+
+#[cfg(feature = "graphics")]
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, OriginDimensions, Size},
+    primitives::{PointsIter, Rectangle},
+    Pixel,
+};
+
+/// Octet index and bit shift of pixel `x` in the pixel data of a line
+fn line_position<P: Panel>(x: u16) -> (usize, u8) {
+    if P::BITS_PER_PIXEL == 1 {
+        ((x >> 3) as usize, 7 - (x & 7) as u8)
+    } else {
+        ((x >> 1) as usize, (x & 1) as u8 * 4)
+    }
+}
+
+/// Octet with every pixel set to the colour
+pub(crate) fn fill_octet<P: Panel>(colour: Palette8) -> u8 {
+    let pixel = P::pixel(colour);
+    if P::BITS_PER_PIXEL == 1 {
+        0u8.wrapping_sub(pixel & 1)
+    } else {
+        pixel << 4 | pixel
+    }
+}
+
+/// Set pixel `x` of the pixel data of a line
+pub(crate) fn write_line_pixel<P: Panel>(line: &mut [u8], x: u16, colour: Palette8) {
+    let (i, shift) = line_position::<P>(x);
+    let mask = ((1u8 << P::BITS_PER_PIXEL) - 1) << shift;
+    line[i] = (line[i] & !mask) | (P::pixel(colour) << shift);
+}
+
+/// Fill `width` pixels of the pixel data of a line starting at `x`
+///
+/// The span is clipped to the display width. Whole octets are written
+/// where possible, only the pixels at unaligned start or end positions
+/// needs a read-modify-write.
+pub(crate) fn fill_line_span<P: Panel>(line: &mut [u8], x: u16, width: u16, colour: Palette8) {
+    if x >= P::WIDTH || width == 0 {
+        return;
+    }
+    let pixels_per_octet = 8 / P::BITS_PER_PIXEL as u16;
+    let mut start = x;
+    let end = core::cmp::min(x as u32 + width as u32, P::WIDTH as u32) as u16;
+    while start < end && start % pixels_per_octet != 0 {
+        write_line_pixel::<P>(line, start, colour);
+        start += 1;
+    }
+    let full_end = end - end % pixels_per_octet;
+    if full_end > start {
+        let c = fill_octet::<P>(colour);
+        let (first, _) = line_position::<P>(start);
+        let (last, _) = line_position::<P>(full_end - 1);
+        for octet in line[first..=last].iter_mut() {
+            *octet = c;
+        }
+        start = full_end;
+    }
+    while start < end {
+        write_line_pixel::<P>(line, start, colour);
+        start += 1;
+    }
+}
+
+/// Frame buffer storage that stays in place, such as a `&'static mut` array
+///
+/// # Safety
+///
+/// The octets must neither move nor be freed while a line is sent from
+/// them, also when the storage itself is moved, such as into a shared
+/// resource.
+pub unsafe trait StaticStorage: AsRef<[u8]> + AsMut<[u8]> {}
+
+unsafe impl StaticStorage for &'static mut [u8] {}
+
+unsafe impl<const N: usize> StaticStorage for &'static mut [u8; N] {}
+
+/// Pixels of a memory in pixel display
+pub struct FrameBuffer<P: Panel, S = <P as Panel>::FrameBuffer> {
+    buffer: S,
+    // octets between the start of two lines
+    stride: usize,
+    // octets before the pixel data of a line
+    offset: usize,
+    // lines drawn to since sent
+    changed: P::LineBits,
+    orientation: Orientation,
+    _panel: PhantomData<P>,
+}
+
+impl<P> Default for FrameBuffer<P>
+where
+    P: Panel,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> FrameBuffer<P>
+where
+    P: Panel,
+{
+    pub fn new() -> Self {
+        let mut frame_buffer = Self {
+            buffer: P::frame_buffer(),
+            stride: P::LINE_OCTETS,
+            offset: 0,
+            changed: P::line_bits(),
+            orientation: Orientation::default(),
+            _panel: PhantomData,
+        };
+        frame_buffer.mark_all_changed();
+        frame_buffer
+    }
+}
+
+impl<P, S> FrameBuffer<P, S>
+where
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Frame buffer using caller provided storage
+    ///
+    /// The storage must be either `P::FRAME_OCTETS` or
+    /// `P::COMMAND_FRAME_OCTETS` long. The line headers and trailers of the
+    /// latter are written here.
+    pub fn with_storage(storage: S) -> Result<Self, Error> {
+        let (stride, offset) = match storage.as_ref().len() {
+            len if len == P::FRAME_OCTETS => (P::LINE_OCTETS, 0),
+            len if len == P::COMMAND_FRAME_OCTETS => (P::LINE_COMMAND_OCTETS, 2),
+            _ => return Err(Error::BufferSizeError),
+        };
+        let mut frame_buffer = Self {
+            buffer: storage,
+            stride,
+            offset,
+            changed: P::line_bits(),
+            orientation: Orientation::default(),
+            _panel: PhantomData,
+        };
+        frame_buffer.mark_all_changed();
+        if frame_buffer.has_commands() {
+            for line in 0..P::HEIGHT {
+                let start = line as usize * P::LINE_COMMAND_OCTETS;
+                let command = &mut frame_buffer.buffer.as_mut()[start..start + stride];
+                command[..2].copy_from_slice(&P::line_header(line));
+                command[2 + P::LINE_OCTETS..].copy_from_slice(&[0, 0]);
+            }
+        }
+        Ok(frame_buffer)
+    }
+
+    /// Release the storage
+    pub fn release(self) -> S {
+        self.buffer
+    }
+
+    /// Is every line stored as a complete line update command?
+    pub fn has_commands(&self) -> bool {
+        self.offset != 0
+    }
+
+    /// The storage octets, including any line headers and trailers
+    pub fn as_bytes(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+
+    /// The octets of a single line
+    pub fn line(&self, line: u16) -> &[u8] {
+        let start = line as usize * self.stride + self.offset;
+        &self.buffer.as_ref()[start..start + P::LINE_OCTETS]
+    }
+
+    /// The octets of a single line, for writing raw pixel data
+    ///
+    /// The line is marked as changed.
+    pub fn line_mut(&mut self, line: u16) -> &mut [u8] {
+        self.mark_changed(line);
+        let start = line as usize * self.stride + self.offset;
+        &mut self.buffer.as_mut()[start..start + P::LINE_OCTETS]
+    }
+
+    /// Is the line drawn to since it was sent?
+    pub fn is_line_changed(&self, line: u16) -> bool {
+        match self.changed.as_ref().get(line as usize / 32) {
+            Some(word) => word & (1 << (line % 32)) != 0,
+            None => false,
+        }
+    }
+
+    /// Mark every line as changed, such as after the display memory was
+    /// cleared
+    pub fn mark_all_changed(&mut self) {
+        for line in 0..P::HEIGHT {
+            self.mark_changed(line);
+        }
+    }
+
+    fn mark_changed(&mut self, line: u16) {
+        if let Some(word) = self.changed.as_mut().get_mut(line as usize / 32) {
+            *word |= 1 << (line % 32);
+        }
+    }
+
+    /// Mark a line as sent, done by the drivers when sending the line
+    pub fn mark_sent(&mut self, line: u16) {
+        if let Some(word) = self.changed.as_mut().get_mut(line as usize / 32) {
+            *word &= !(1 << (line % 32));
+        }
+    }
+
+    /// The complete line update command of a line, if stored
+    pub fn line_command(&self, line: u16) -> Option<&[u8]> {
+        if self.has_commands() {
+            let start = line as usize * self.stride;
+            Some(&self.buffer.as_ref()[start..start + self.stride])
+        } else {
+            None
+        }
+    }
+
+    /// Set the orientation used when drawing
+    ///
+    /// Already drawn content is not moved.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Width of the display in the current orientation
+    pub fn width(&self) -> u16 {
+        self.orientation.size(P::WIDTH, P::HEIGHT).0
+    }
+
+    /// Height of the display in the current orientation
+    pub fn height(&self) -> u16 {
+        self.orientation.size(P::WIDTH, P::HEIGHT).1
+    }
+
+    /// Set a pixel, the coordinates are in the current orientation
+    pub fn set_pixel(&mut self, x: u16, y: u16, colour: Palette8) {
+        let (x, y) = self.orientation.transform(x, y, P::WIDTH, P::HEIGHT);
+        self.write_pixel(x, y, colour);
+    }
+
+    /// Frame buffer octet index and bit shift of a pixel in display coordinates
+    fn pixel_position(&self, x: u16, y: u16) -> (usize, u8) {
+        let (i, shift) = line_position::<P>(x);
+        (y as usize * self.stride + self.offset + i, shift)
+    }
+
+    /// Set a pixel in display coordinates
+    pub(crate) fn write_pixel(&mut self, x: u16, y: u16, colour: Palette8) {
+        write_line_pixel::<P>(self.line_mut(y), x, colour);
+    }
+
+    /// Set two horizontally adjacent pixels in display coordinates on a
+    /// 4-bit panel, `x` must be even
+    fn set_pixel_pair(&mut self, x: u16, y: u16, first: Palette8, second: Palette8) {
+        self.mark_changed(y);
+        let (i, _) = self.pixel_position(x, y);
+        self.buffer.as_mut()[i] = P::pixel(second) << 4 | P::pixel(first);
+    }
+
+    /// Fill the whole frame buffer with a single colour
+    pub fn fill(&mut self, colour: Palette8) {
+        let c = fill_octet::<P>(colour);
+        for line in 0..P::HEIGHT {
+            for octet in self.line_mut(line).iter_mut() {
+                *octet = c;
+            }
+        }
+    }
+
+    /// Copy the pixels of another frame buffer of the same panel
+    ///
+    /// The orientation is not copied. The lines that differ and the lines
+    /// changed in `other` are marked as changed.
+    pub fn copy_from<T>(&mut self, other: &FrameBuffer<P, T>)
+    where
+        T: AsRef<[u8]> + AsMut<[u8]>,
+    {
+        for line in 0..P::HEIGHT {
+            if other.is_line_changed(line) || self.line(line) != other.line(line) {
+                self.line_mut(line).copy_from_slice(other.line(line));
+            }
+        }
+    }
+
+    /// Mark the lines changed in `other` as changed, and no other lines
+    ///
+    /// For a copy of `other` with the same content on the display once
+    /// `other` is sent.
+    pub(crate) fn copy_changed_from<T>(&mut self, other: &FrameBuffer<P, T>)
+    where
+        T: AsRef<[u8]> + AsMut<[u8]>,
+    {
+        self.changed
+            .as_mut()
+            .copy_from_slice(other.changed.as_ref());
+    }
+
+    /// Fill a rectangle with a single colour, the coordinates are in the
+    /// current orientation
+    ///
+    /// The rectangle is clipped to the display.
+    pub fn fill_rect(&mut self, x: u16, y: u16, width: u16, height: u16, colour: Palette8) {
+        let (w, h) = (self.width(), self.height());
+        if x >= w || y >= h || width == 0 || height == 0 {
+            return;
+        }
+        let x_end = core::cmp::min(x as u32 + width as u32, w as u32) as u16 - 1;
+        let y_end = core::cmp::min(y as u32 + height as u32, h as u32) as u16 - 1;
+        // A rotated or mirrored rectangle is still a rectangle
+        let (x0, y0) = self.orientation.transform(x, y, P::WIDTH, P::HEIGHT);
+        let (x1, y1) = self
+            .orientation
+            .transform(x_end, y_end, P::WIDTH, P::HEIGHT);
+        let (left, right) = (core::cmp::min(x0, x1), core::cmp::max(x0, x1));
+        let (top, bottom) = (core::cmp::min(y0, y1), core::cmp::max(y0, y1));
+        for line in top..=bottom {
+            self.fill_span(left, line, right - left + 1, colour);
+        }
+    }
+
+    /// Fill `width` pixels of line `y` starting at `x` with a single colour,
+    /// in display coordinates
+    ///
+    /// The span is clipped to the display width.
+    pub(crate) fn fill_span(&mut self, x: u16, y: u16, width: u16, colour: Palette8) {
+        if y < P::HEIGHT {
+            fill_line_span::<P>(self.line_mut(y), x, width, colour);
+        }
+    }
+
+    /// Get a pixel, the coordinates are in the current orientation
+    pub fn get_pixel(&self, x: u16, y: u16) -> Palette8 {
+        let (x, y) = self.orientation.transform(x, y, P::WIDTH, P::HEIGHT);
+        self.read_pixel(x, y)
+    }
+
+    /// Get a pixel in display coordinates
+    pub fn read_pixel(&self, x: u16, y: u16) -> Palette8 {
+        let (i, shift) = self.pixel_position(x, y);
+        let mask = (1u8 << P::BITS_PER_PIXEL) - 1;
+        P::colour((self.buffer.as_ref()[i] >> shift) & mask)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<P, S> DrawTarget for FrameBuffer<P, S>
+where
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+{
+    type Color = Palette8;
+    // `ExampleDisplay` uses a framebuffer and doesn't need to communicate with the display
+    // controller to draw pixel, which means that drawing operations can never fail. To reflect
+    // this the type `Infallible` was chosen as the `Error` type.
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let x_limit = self.width() as i32 - 1;
+        let y_limit = self.height() as i32 - 1;
+        for Pixel(coord, colour) in pixels.into_iter() {
+            // Check if the pixel coordinates are out of bounds (negative or greater than
+            // the display size). `DrawTarget` implementation are required to discard any out of
+            // bounds pixels without returning an error or causing a panic.
+            if (0..=x_limit).contains(&coord.x) && (0..=y_limit).contains(&coord.y) {
+                self.set_pixel(coord.x as u16, coord.y as u16, colour);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colours: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if !self.orientation.is_identity() {
+            return self.draw_iter(
+                area.points()
+                    .zip(colours)
+                    .map(|(point, colour)| Pixel(point, colour)),
+            );
+        }
+        let display_width = P::WIDTH as i32;
+        let display_height = P::HEIGHT as i32;
+        let width = area.size.width as i32;
+        let mut colours = colours.into_iter();
+        for y in area.rows() {
+            if !(0..display_height).contains(&y) {
+                // Consume the colours of lines outside the display
+                for _ in 0..width {
+                    if colours.next().is_none() {
+                        return Ok(());
+                    }
+                }
+                continue;
+            }
+            let end = area.top_left.x + width;
+            let mut x = area.top_left.x;
+            while x < end {
+                let colour = match colours.next() {
+                    Some(c) => c,
+                    None => return Ok(()),
+                };
+                if (0..display_width).contains(&x) {
+                    // Write whole octets when both pixels are on the display
+                    if P::BITS_PER_PIXEL == 4 && x & 1 == 0 && x + 1 < end && x + 1 < display_width
+                    {
+                        let second = match colours.next() {
+                            Some(c) => c,
+                            None => {
+                                self.write_pixel(x as u16, y as u16, colour);
+                                return Ok(());
+                            }
+                        };
+                        self.set_pixel_pair(x as u16, y as u16, colour, second);
+                        x += 2;
+                        continue;
+                    }
+                    self.write_pixel(x as u16, y as u16, colour);
+                }
+                x += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, colour: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        if area.size.width == 0 || area.size.height == 0 {
+            return Ok(());
+        }
+        self.fill_rect(
+            area.top_left.x as u16,
+            area.top_left.y as u16,
+            area.size.width as u16,
+            area.size.height as u16,
+            colour,
+        );
+        Ok(())
+    }
+
+    fn clear(&mut self, colour: Self::Color) -> Result<(), Self::Error> {
+        self.fill(colour);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<P, S> OriginDimensions for FrameBuffer<P, S>
+where
+    P: Panel,
+{
+    fn size(&self) -> Size {
+        let (width, height) = self.orientation.size(P::WIDTH, P::HEIGHT);
+        Size::new(width as u32, height as u32)
+    }
+}
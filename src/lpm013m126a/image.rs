@@ -0,0 +1,233 @@
+//! # Run length encoded images
+//!
+//! Compact format for `Palette8` pictures stored in flash, such as splash
+//! screens and icons. A full screen picture with large areas of a single
+//! colour is typically a few hundred octets instead of the 15488 octets of
+//! the frame buffer.
+//!
+//! | Octets | Content                                  |
+//! |--------|------------------------------------------|
+//! | 2      | Magic, `P8`                              |
+//! | 2      | Width in pixels, little endian           |
+//! | 2      | Height in pixels, little endian          |
+//! | ...    | Runs of pixels in row order              |
+//!
+//! Each run starts with an octet holding the colour index in the three most
+//! significant bits and the run length in the five least significant bits.
+//! Lengths 0 to 30 encode runs of 1 to 31 pixels. Length 31 is followed by
+//! an octet holding the run length minus 32, for runs of 32 to 287 pixels.
+//! Runs continue over line ends.
+//!
+//! [`RleImage`] decodes the runs while drawing and can be drawn at any
+//! position using `embedded_graphics::image::Image`. Images are created
+//! with [`encode`] or the `image_convert` example, which converts PNG images.
+//! [`Encoder`] encodes without allocating, for screenshots of the frame
+//! buffer.
+
+use crate::{lpm013m126a::Palette8, Error};
+use microbit_two_convert::SHORT_RUN;
+
+#[cfg(feature = "graphics")]
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, OriginDimensions, Point, Size},
+    image::ImageDrawable,
+    primitives::Rectangle,
+    Pixel,
+};
+
+/// First octets of an image
+pub const MAGIC: [u8; 2] = *b"P8";
+
+/// Octets of the header before the runs
+pub const HEADER_OCTETS: usize = 6;
+
+/// Run length encoded `Palette8` image
+#[derive(Clone, Copy)]
+pub struct RleImage<'a> {
+    width: u16,
+    height: u16,
+    runs: &'a [u8],
+}
+
+impl<'a> RleImage<'a> {
+    /// Image of encoded data
+    ///
+    /// The data is validated, runs must cover exactly every pixel of the
+    /// image.
+    pub fn new(data: &'a [u8]) -> Result<Self, Error> {
+        if data.len() < HEADER_OCTETS || data[..2] != MAGIC {
+            return Err(Error::FormatError);
+        }
+        let image = Self {
+            width: u16::from_le_bytes([data[2], data[3]]),
+            height: u16::from_le_bytes([data[4], data[5]]),
+            runs: &data[HEADER_OCTETS..],
+        };
+        let mut pixels = 0usize;
+        let mut runs = image.runs();
+        for (_, length) in &mut runs {
+            pixels += length as usize;
+        }
+        if runs.truncated || pixels != image.width as usize * image.height as usize {
+            return Err(Error::FormatError);
+        }
+        Ok(image)
+    }
+
+    /// Image of runs validated when they were created, used by
+    /// `include_palette8_image!`
+    #[doc(hidden)]
+    pub const fn from_raw_parts(width: u16, height: u16, runs: &'a [u8]) -> Self {
+        Self {
+            width,
+            height,
+            runs,
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The runs of the image, colour and number of pixels
+    pub fn runs(&self) -> Runs<'a> {
+        Runs {
+            data: self.runs,
+            truncated: false,
+        }
+    }
+
+    /// The pixels of the image in row order
+    pub fn pixels(&self) -> impl Iterator<Item = Palette8> + 'a {
+        self.runs()
+            .flat_map(|(colour, length)| (0..length).map(move |_| colour))
+    }
+}
+
+/// Iterator over the runs of an image
+pub struct Runs<'a> {
+    data: &'a [u8],
+    // a long run without its length octet
+    truncated: bool,
+}
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = (Palette8, u16);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&octet, rest) = self.data.split_first()?;
+        let colour = Palette8::from(octet >> 5);
+        let length = (octet & 0b1_1111) as u16;
+        if length < SHORT_RUN {
+            self.data = rest;
+            return Some((colour, length + 1));
+        }
+        match rest.split_first() {
+            Some((&extra, rest)) => {
+                self.data = rest;
+                Some((colour, SHORT_RUN + 1 + extra as u16))
+            }
+            None => {
+                self.data = rest;
+                self.truncated = true;
+                None
+            }
+        }
+    }
+}
+
+/// Run length encoder producing runs while pixels are added
+///
+/// Used without allocation, such as for screenshots on the device.
+#[derive(Default)]
+pub struct Encoder {
+    encoder: microbit_two_convert::Encoder,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next pixel, completed runs are passed to `write`
+    pub fn push<F>(&mut self, colour: Palette8, write: F)
+    where
+        F: FnMut(&[u8]),
+    {
+        self.encoder.push(u8::from(colour), write);
+    }
+
+    /// Pass the last run to `write`
+    pub fn finish<F>(&mut self, write: F)
+    where
+        F: FnMut(&[u8]),
+    {
+        self.encoder.finish(write);
+    }
+}
+
+/// The header of an image
+pub fn header(width: u16, height: u16) -> [u8; HEADER_OCTETS] {
+    let [w0, w1] = width.to_le_bytes();
+    let [h0, h1] = height.to_le_bytes();
+    [MAGIC[0], MAGIC[1], w0, w1, h0, h1]
+}
+
+/// Encode pixels in row order
+#[cfg(feature = "std")]
+pub fn encode<I>(width: u16, height: u16, pixels: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = Palette8>,
+{
+    let mut data = Vec::new();
+    data.extend_from_slice(&header(width, height));
+    let mut encoder = Encoder::new();
+    let total = width as usize * height as usize;
+    for colour in pixels.into_iter().take(total) {
+        encoder.push(colour, |octets| data.extend_from_slice(octets));
+    }
+    encoder.finish(|octets| data.extend_from_slice(octets));
+    data
+}
+
+#[cfg(feature = "graphics")]
+impl<'a> OriginDimensions for RleImage<'a> {
+    fn size(&self) -> Size {
+        Size::new(self.width as u32, self.height as u32)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<'a> ImageDrawable for RleImage<'a> {
+    type Color = Palette8;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        target.fill_contiguous(&self.bounding_box(), self.pixels())
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        // The whole image is decoded, only the pixels inside the area drawn
+        let area = area.intersection(&self.bounding_box());
+        let width = self.width as i32;
+        let pixels = self.pixels().enumerate().filter_map(move |(i, colour)| {
+            let point = Point::new(i as i32 % width, i as i32 / width);
+            if area.contains(point) {
+                Some(Pixel(point - area.top_left, colour))
+            } else {
+                None
+            }
+        });
+        target.draw_iter(pixels)
+    }
+}
@@ -0,0 +1,199 @@
+//! # Memory in pixel display without frame buffer
+//!
+//! [`LineDisplay`] keeps a single line buffer instead of a frame buffer. The
+//! pixels of each line are produced by a [`LineRenderer`] from
+//! `spi_task_event()`, just before the line is sent. This suits procedurally
+//! drawn content, such as gradients, charts or tiles, when the RAM of a
+//! frame buffer is not available.
+//!
+//! The renderer is called from the SPI interrupt and should be quick, as the
+//! next line is not sent until it returns.
+//!
+//! Renderers that know which lines changed, such as the sprite
+//! [`Scene`](super::sprite::Scene), implement
+//! [`LineRenderer::is_line_changed`] and only those lines are sent.
+
+use crate::{
+    lpm013m126a::{
+        frame_buffer::{fill_line_span, fill_octet, write_line_pixel},
+        line_stream::{LineSource, LineStream},
+        Command, FrameStats, Palette8, Panel,
+    },
+    spim::{Instance, Spim},
+    Error,
+};
+use core::marker::PhantomData;
+use embedded_hal::digital::v2::OutputPin;
+
+/// Pixel data of a single line, in display coordinates
+pub struct Line<'a, P: Panel> {
+    data: &'a mut [u8],
+    _panel: PhantomData<P>,
+}
+
+impl<'a, P> Line<'a, P>
+where
+    P: Panel,
+{
+    /// The octets of the line, `P::LINE_OCTETS` long
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.data
+    }
+
+    /// Set a pixel, pixels outside the line are ignored
+    pub fn set_pixel(&mut self, x: u16, colour: Palette8) {
+        if x < P::WIDTH {
+            write_line_pixel::<P>(self.data, x, colour);
+        }
+    }
+
+    /// Fill `width` pixels starting at `x` with a single colour
+    pub fn fill_span(&mut self, x: u16, width: u16, colour: Palette8) {
+        fill_line_span::<P>(self.data, x, width, colour);
+    }
+
+    /// Fill the whole line with a single colour
+    pub fn fill(&mut self, colour: Palette8) {
+        let octet = fill_octet::<P>(colour);
+        for o in self.data.iter_mut() {
+            *o = octet;
+        }
+    }
+}
+
+/// Producer of the pixels of each line
+///
+/// Implemented for closures taking the line number and the line pixels.
+pub trait LineRenderer<P: Panel> {
+    /// Render `line`, the pixel data holds the previously rendered line
+    fn render_line(&mut self, line: u16, pixels: &mut Line<P>);
+
+    /// Has `line` changed since it was last rendered? Unchanged lines are
+    /// skipped when sending a frame.
+    fn is_line_changed(&self, _line: u16) -> bool {
+        true
+    }
+}
+
+impl<P, F> LineRenderer<P> for F
+where
+    P: Panel,
+    F: FnMut(u16, &mut Line<P>),
+{
+    fn render_line(&mut self, line: u16, pixels: &mut Line<P>) {
+        self(line, pixels)
+    }
+}
+
+/// Renderer as the line source of the stream
+struct Rendered<'a, R>(&'a mut R);
+
+impl<'a, P, R> LineSource<P> for Rendered<'a, R>
+where
+    P: Panel,
+    R: LineRenderer<P>,
+{
+    fn is_line_changed(&self, line: u16) -> bool {
+        self.0.is_line_changed(line)
+    }
+
+    fn write_line(&mut self, line: u16, pixels: &mut [u8]) {
+        let mut pixels = Line {
+            data: pixels,
+            _panel: PhantomData,
+        };
+        self.0.render_line(line, &mut pixels);
+    }
+}
+
+/// Driver for memory in pixel displays rendering one line at a time
+pub struct LineDisplay<SPI: Instance, DISP, P: Panel, R> {
+    stream: LineStream<SPI, DISP, P>,
+    renderer: R,
+}
+
+impl<SPI, DISP, P, R> LineDisplay<SPI, DISP, P, R>
+where
+    SPI: Instance,
+    DISP: OutputPin,
+    P: Panel,
+    R: LineRenderer<P>,
+{
+    /// Driver sending each line from the `'static` `line_buffer`
+    pub fn new(
+        spi: Spim<SPI>,
+        display: DISP,
+        line_buffer: &'static mut P::LineBuffer,
+        renderer: R,
+    ) -> Self {
+        Self {
+            stream: LineStream::new(spi, display, line_buffer),
+            renderer,
+        }
+    }
+
+    /// Release the peripherals, the line buffer and the renderer, once the
+    /// frame is sent
+    pub fn release(mut self) -> (Spim<SPI>, DISP, &'static mut P::LineBuffer, R) {
+        let (spi, display, line_buffer) = self.stream.release(&mut Rendered(&mut self.renderer));
+        (spi, display, line_buffer, self.renderer)
+    }
+
+    pub fn init(&mut self) -> Result<(), Error> {
+        self.stream.init()
+    }
+
+    /// Handle the SPI end of transfer event, rendering and sending the next
+    /// changed line
+    pub fn spi_task_event(&mut self) {
+        self.stream
+            .spi_task_event(&mut Rendered(&mut self.renderer));
+    }
+
+    /// Send the rest of the frame or command without the SPI interrupt
+    pub fn flush(&mut self) {
+        self.stream.flush(&mut Rendered(&mut self.renderer));
+    }
+
+    /// Clear the display memory, fails with `BusyError` while a frame is
+    /// sent
+    pub fn send_clear(&mut self) -> Result<(), Error> {
+        self.stream.send_command(Command::Clear)
+    }
+
+    /// Is a frame being sent to the display?
+    pub fn is_drawing(&self) -> bool {
+        self.stream.is_drawing()
+    }
+
+    pub fn renderer(&self) -> &R {
+        &self.renderer
+    }
+
+    /// The renderer, changes are shown from the next rendered line
+    pub fn renderer_mut(&mut self) -> &mut R {
+        &mut self.renderer
+    }
+
+    /// Timing of the sent frames
+    pub fn stats(&self) -> &FrameStats {
+        self.stream.stats()
+    }
+
+    /// Clock used to time frames, see [`FrameStats`]
+    pub fn set_clock(&mut self, clock: fn() -> u32, ticks_per_second: u32) {
+        self.stream.stats_mut().set_clock(clock, ticks_per_second);
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stream.stats_mut().reset();
+    }
+
+    /// Render and send all changed lines
+    ///
+    /// If a frame is being sent, another frame is sent after it.
+    pub fn update_display(&mut self) -> Result<(), Error> {
+        self.stream
+            .update_display(&mut Rendered(&mut self.renderer))
+    }
+}
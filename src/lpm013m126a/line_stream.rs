@@ -0,0 +1,421 @@
+//! # Line streaming shared by the display drivers
+//!
+//! [`LineStream`] sends commands and frames of line updates to a memory in
+//! pixel display, one line per SPI end of transfer event. The pixels of each
+//! line come from a [`LineSource`], the frame buffer of
+//! [`MipDisplay`](super::MipDisplay) or the renderer of
+//! [`LineDisplay`](super::LineDisplay).
+//!
+//! Each line is written to a `'static` line buffer, owned by the SPIM
+//! transfer while it is sent, so the driver can be moved at any time. Lines
+//! stored as line update commands in
+//! [`StaticStorage`](super::StaticStorage) are sent from the frame buffer
+//! instead, drawing into such a line while it is sent changes what is sent.
+
+use core::mem;
+
+use crate::{
+    lpm013m126a::{Command, FrameBuffer, FrameStats, Panel},
+    spim::{Instance, Spim, Transfer},
+    DmaSlice, Error,
+};
+use embedded_dma::ReadBuffer;
+use embedded_hal::digital::v2::OutputPin;
+
+/// Producer of the lines sent by a [`LineStream`]
+pub(crate) trait LineSource<P: Panel> {
+    /// Is `line` sent with the next frame?
+    fn is_line_changed(&self, _line: u16) -> bool {
+        true
+    }
+
+    /// The complete line update command of `line`, if stored, the line is
+    /// sent from it
+    ///
+    /// The command must stay in place until the transfer has ended, see
+    /// [`StaticStorage`](super::StaticStorage).
+    fn line_command(&mut self, _line: u16) -> Option<&[u8]> {
+        None
+    }
+
+    /// Write the pixel data of `line`, `P::LINE_OCTETS` long, to send it
+    fn write_line(&mut self, line: u16, pixels: &mut [u8]);
+}
+
+impl<P, S> LineSource<P> for FrameBuffer<P, S>
+where
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+{
+    fn is_line_changed(&self, line: u16) -> bool {
+        FrameBuffer::is_line_changed(self, line)
+    }
+
+    fn line_command(&mut self, line: u16) -> Option<&[u8]> {
+        self.mark_sent(line);
+        FrameBuffer::line_command(self, line)
+    }
+
+    fn write_line(&mut self, line: u16, pixels: &mut [u8]) {
+        self.mark_sent(line);
+        pixels.copy_from_slice(self.line(line));
+    }
+}
+
+/// The line buffer, of which the first `len` octets are sent, unless a
+/// stored line command is sent
+pub(crate) struct LineBuffer<B: 'static> {
+    data: &'static mut B,
+    len: usize,
+    stored: Option<DmaSlice>,
+}
+
+unsafe impl<B> ReadBuffer for LineBuffer<B>
+where
+    B: AsRef<[u8]>,
+{
+    type Word = u8;
+
+    unsafe fn read_buffer(&self) -> (*const u8, usize) {
+        if let Some(command) = &self.stored {
+            return (command.ptr as *const u8, command.len as usize);
+        }
+        let data = self.data.as_ref();
+        (data.as_ptr(), self.len.min(data.len()))
+    }
+}
+
+/// The SPIM and the line buffer, both owned by the transfer while sending
+enum Bus<SPI: Instance, B: 'static> {
+    Idle(Spim<SPI>, LineBuffer<B>),
+    Sending(Transfer<SPI, LineBuffer<B>, ()>),
+    Released,
+}
+
+const FLAGS_NONE: u32 = 0x0000_0000;
+const FLAGS_DRAWING: u32 = 0x0000_0001;
+const FLAGS_UPDATE: u32 = 0x0000_0002;
+const FLAGS_HOLD: u32 = 0x0000_0004;
+const FLAGS_COMMAND: u32 = 0x0000_0008;
+
+/// State of the line transfers to a display
+pub(crate) struct LineStream<SPI: Instance, DISP, P: Panel> {
+    bus: Bus<SPI, P::LineBuffer>,
+    display: Option<DISP>, // taken when released
+    flags: u32,
+    current_line: u16,
+    stats: FrameStats,
+}
+
+impl<SPI, DISP, P> LineStream<SPI, DISP, P>
+where
+    SPI: Instance,
+    DISP: OutputPin,
+    P: Panel,
+{
+    pub fn new(spi: Spim<SPI>, display: DISP, buffer: &'static mut P::LineBuffer) -> Self {
+        Self {
+            bus: Bus::Idle(
+                spi,
+                LineBuffer {
+                    data: buffer,
+                    len: 0,
+                    stored: None,
+                },
+            ),
+            display: Some(display),
+            flags: FLAGS_NONE,
+            current_line: 0,
+            stats: FrameStats::new(),
+        }
+    }
+
+    /// Send the rest of the frame and release the peripherals and the line
+    /// buffer
+    pub fn release<L: LineSource<P>>(
+        mut self,
+        source: &mut L,
+    ) -> (Spim<SPI>, DISP, &'static mut P::LineBuffer) {
+        self.flush(source);
+        match (
+            mem::replace(&mut self.bus, Bus::Released),
+            self.display.take(),
+        ) {
+            (Bus::Idle(spi, buffer), Some(display)) => (spi, display, buffer.data),
+            // flushed and released only once
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn init(&mut self) -> Result<(), Error> {
+        let display = self.display.as_mut().ok_or(Error::DisplayError)?;
+        display.set_low().map_err(|_| Error::DisplayError)?;
+        display.set_high().map_err(|_| Error::DisplayError)?;
+        Ok(())
+    }
+
+    /// Handle the SPI end of transfer event, starting the next line
+    pub fn spi_task_event<L: LineSource<P>>(&mut self, source: &mut L) {
+        match &self.bus {
+            Bus::Sending(transfer) if transfer.is_done() => (),
+            // the event was handled by `flush()`
+            _ => return,
+        }
+        self.end_transfer();
+        self.transfer_done(source);
+    }
+
+    /// Send the rest of the frame or command without the SPI interrupt
+    pub fn flush<L: LineSource<P>>(&mut self, source: &mut L) {
+        while (self.flags & (FLAGS_DRAWING | FLAGS_COMMAND)) != 0 {
+            self.end_transfer();
+            self.transfer_done(source);
+        }
+    }
+
+    fn enable_spi(&mut self) {
+        if let Bus::Idle(spi, _) = &mut self.bus {
+            spi.enable();
+        }
+    }
+
+    /// Disable the SPIM, unless a transfer owns it
+    fn disable_spi(&mut self) {
+        if let Bus::Idle(spi, _) = &mut self.bus {
+            spi.disable();
+        }
+    }
+
+    fn transfer_done<L: LineSource<P>>(&mut self, source: &mut L) {
+        if (self.flags & FLAGS_COMMAND) == FLAGS_COMMAND {
+            // end of a command such as clear
+            self.flags &= !FLAGS_COMMAND;
+            if (self.flags & FLAGS_HOLD) == FLAGS_HOLD {
+                self.disable_spi();
+            } else if (self.flags & FLAGS_UPDATE) == FLAGS_UPDATE {
+                let _ = self.start_frame(source);
+            }
+            return;
+        }
+        if (self.flags & FLAGS_DRAWING) == 0 {
+            return;
+        }
+        if let Some(line) = Self::next_changed_line(source, self.current_line + 1) {
+            self.current_line = line;
+            let _ = self.send_line(source, line);
+            return;
+        }
+        self.stats.frame_end();
+        self.current_line = 0;
+        if (self.flags & FLAGS_HOLD) == FLAGS_HOLD {
+            // further updates are sent when resumed
+            self.flags &= !FLAGS_DRAWING;
+            self.disable_spi();
+        } else if (self.flags & FLAGS_UPDATE) == FLAGS_UPDATE {
+            // an update requested while the frame was sent
+            let _ = self.start_frame(source);
+        } else {
+            self.flags &= !FLAGS_DRAWING;
+        }
+    }
+
+    /// Start sending the changed lines, the pending update is taken
+    fn start_frame<L: LineSource<P>>(&mut self, source: &mut L) -> Result<(), Error> {
+        self.flags &= !FLAGS_UPDATE;
+        match Self::next_changed_line(source, 0) {
+            Some(line) => {
+                self.flags |= FLAGS_DRAWING;
+                self.stats.frame_start();
+                self.current_line = line;
+                self.send_line(source, line)
+            }
+            None => {
+                self.flags &= !FLAGS_DRAWING;
+                Ok(())
+            }
+        }
+    }
+
+    /// The first changed line from `line`
+    fn next_changed_line<L: LineSource<P>>(source: &L, line: u16) -> Option<u16> {
+        (line..P::HEIGHT).find(|&l| source.is_line_changed(l))
+    }
+
+    /// The line buffer to write, unless a transfer owns it
+    fn idle_buffer(&mut self) -> Result<&mut [u8], Error> {
+        match &mut self.bus {
+            Bus::Idle(_, buffer) => Ok(buffer.data.as_mut()),
+            _ => Err(Error::BusyError),
+        }
+    }
+
+    /// Send the first `len` octets of the line buffer, or the `stored` line
+    /// command
+    fn send_buffer(&mut self, len: usize, stored: Option<DmaSlice>) -> Result<(), Error> {
+        match mem::replace(&mut self.bus, Bus::Released) {
+            Bus::Idle(spi, mut buffer) => {
+                buffer.len = len;
+                buffer.stored = stored;
+                match spi.dma_write(buffer) {
+                    Ok(transfer) => {
+                        self.bus = Bus::Sending(transfer);
+                        Ok(())
+                    }
+                    Err((_, spi, buffer)) => {
+                        self.bus = Bus::Idle(spi, buffer);
+                        Err(Error::BusWriteError)
+                    }
+                }
+            }
+            bus => {
+                self.bus = bus;
+                Err(Error::BusyError)
+            }
+        }
+    }
+
+    fn send_line<L: LineSource<P>>(&mut self, source: &mut L, line: u16) -> Result<(), Error> {
+        self.stats.line_sent();
+        if let Some(command) = source.line_command(line) {
+            // The storage stays in place while the line is sent
+            let command = DmaSlice::from_slice(command);
+            return self.send_buffer(0, Some(command));
+        }
+        let buffer = self.idle_buffer()?;
+        buffer[..2].copy_from_slice(&P::line_header(line));
+        source.write_line(line, &mut buffer[2..2 + P::LINE_OCTETS]);
+        buffer[2 + P::LINE_OCTETS] = 0;
+        buffer[2 + P::LINE_OCTETS + 1] = 0;
+        self.send_buffer(P::LINE_COMMAND_OCTETS, None)
+    }
+
+    /// Send a command from the line buffer
+    ///
+    /// Fails with `BusyError` while a frame is sent or while held.
+    pub fn send_command(&mut self, command: Command) -> Result<(), Error> {
+        if (self.flags & (FLAGS_DRAWING | FLAGS_COMMAND | FLAGS_HOLD)) != 0 {
+            return Err(Error::BusyError);
+        }
+        let command = P::command(command).ok_or(Error::UnsupportedError)?;
+        self.idle_buffer()?[..2].copy_from_slice(&[command, 0]);
+        self.flags |= FLAGS_COMMAND;
+        self.send_buffer(2, None)
+    }
+
+    /// Is a frame being sent to the display?
+    pub fn is_drawing(&self) -> bool {
+        (self.flags & FLAGS_DRAWING) == FLAGS_DRAWING
+    }
+
+    /// Send the changed lines, after the frame or command being sent
+    ///
+    /// While held, the frame is sent when resumed.
+    pub fn update_display<L: LineSource<P>>(&mut self, source: &mut L) -> Result<(), Error> {
+        self.stats
+            .request((self.flags & FLAGS_UPDATE) == FLAGS_UPDATE);
+        self.flags |= FLAGS_UPDATE;
+        if (self.flags & (FLAGS_DRAWING | FLAGS_HOLD | FLAGS_COMMAND)) == 0 {
+            self.start_frame(source)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Stop sending lines and disable the SPIM, once the frame or command
+    /// being sent is completed
+    pub fn hold(&mut self) {
+        self.flags |= FLAGS_HOLD;
+        if (self.flags & (FLAGS_DRAWING | FLAGS_COMMAND)) == 0 {
+            self.disable_spi();
+        }
+    }
+
+    /// Is the display held, with the SPIM disabled?
+    pub fn is_held(&self) -> bool {
+        (self.flags & (FLAGS_HOLD | FLAGS_DRAWING | FLAGS_COMMAND)) == FLAGS_HOLD
+    }
+
+    /// Enable the SPIM and send any update requested while held
+    pub fn resume<L: LineSource<P>>(&mut self, source: &mut L) -> Result<(), Error> {
+        if (self.flags & FLAGS_HOLD) == 0 {
+            return Ok(());
+        }
+        self.flags &= !FLAGS_HOLD;
+        if (self.flags & FLAGS_DRAWING) == FLAGS_DRAWING {
+            // the frame being sent continues
+            return Ok(());
+        }
+        self.enable_spi();
+        if (self.flags & FLAGS_UPDATE) == FLAGS_UPDATE {
+            self.start_frame(source)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Timing of the sent frames
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    pub fn stats_mut(&mut self) -> &mut FrameStats {
+        &mut self.stats
+    }
+
+    /// Clear the display memory, set DISP low and disable the SPIM
+    pub fn power_off(&mut self) -> Result<(), Error> {
+        if (self.flags & (FLAGS_DRAWING | FLAGS_COMMAND)) != 0 {
+            return Err(Error::BusyError);
+        }
+        if (self.flags & FLAGS_HOLD) == FLAGS_HOLD {
+            self.enable_spi();
+            self.flags &= !FLAGS_HOLD;
+        }
+        let result = self.send_command(Command::Clear);
+        if result.is_ok() {
+            self.end_transfer();
+            self.flags &= !FLAGS_COMMAND;
+        }
+        self.flags |= FLAGS_HOLD;
+        self.disable_spi();
+        result?;
+        let display = self.display.as_mut().ok_or(Error::DisplayError)?;
+        display.set_low().map_err(|_| Error::DisplayError)
+    }
+
+    /// Set DISP high after `power_off` and send a frame
+    pub fn power_on<L: LineSource<P>>(&mut self, source: &mut L) -> Result<(), Error> {
+        let display = self.display.as_mut().ok_or(Error::DisplayError)?;
+        display.set_high().map_err(|_| Error::DisplayError)?;
+        self.flags |= FLAGS_UPDATE;
+        self.resume(source)
+    }
+}
+
+impl<SPI, DISP, P> LineStream<SPI, DISP, P>
+where
+    SPI: Instance,
+    P: Panel,
+{
+    /// Wait for the transfer to end, taking back the SPIM and the line buffer
+    fn end_transfer(&mut self) {
+        self.bus = match mem::replace(&mut self.bus, Bus::Released) {
+            Bus::Sending(transfer) => {
+                let (spi, buffer, ()) = transfer.wait();
+                Bus::Idle(spi, buffer)
+            }
+            bus => bus,
+        };
+    }
+}
+
+impl<SPI, DISP, P> Drop for LineStream<SPI, DISP, P>
+where
+    SPI: Instance,
+    P: Panel,
+{
+    /// Finish the line or command being sent, the rest of the frame is not
+    fn drop(&mut self) {
+        self.end_transfer();
+    }
+}
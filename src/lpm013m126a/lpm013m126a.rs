@@ -1,228 +1,308 @@
 //! # JDI LPM013M126A
 //! SPI 8 colour memory in pixel (MIP) display
 //!
+//! The driver is generic over the [`Panel`] and also handles other JDI and
+//! Sharp memory in pixel displays.
+//!
 //! ## Connection
 //!
 //!
 //!
+//! ## Power
+//!
+//! The panel keeps its image without being refreshed, only the VCOM signal
+//! on EXTCOMIN needs to toggle. [`MipDisplay::hold`] stops sending lines and
+//! disables the SPIM, together with [`Vcom`](crate::Vcom) toggling EXTCOMIN
+//! from the RTC a static screen costs microamps.
+//!
+//! [`MipDisplay::power_off`] follows the power off sequence of the panel,
+//! the memory is cleared and DISP is set low. Afterwards VCOM is stopped and
+//! the panel supply can be removed.
 
 use crate::{
-    lpm013m126a::Palette8,
+    lpm013m126a::{
+        line_stream::LineStream, panel, screenshot, Command, FrameBuffer, FrameStats, Orientation,
+        Palette8, Panel, StaticStorage,
+    },
     spim::{Instance, Spim},
-    DmaSlice, Error,
+    Error,
 };
-use core::convert::{From, TryInto};
 use embedded_hal::digital::v2::OutputPin;
 
 #[cfg(feature = "graphics")]
 use embedded_graphics::{
     draw_target::DrawTarget,
     geometry::{OriginDimensions, Size},
+    primitives::Rectangle,
     Pixel,
 };
 
-/// SPI commands for the JDI LPM013M126A
-/// The command layout is
-pub enum Command {
-    /// Update the specified line in 3-bit colour mode
-    DrawLines3bit,
-    /// Update the specified line in 1-bit colour mode
-    DrawLines1bit,
-    /// Update the specified line in 4-bit colour mode
-    DrawLines4bit,
-    /// No update
-    NoUpdate,
-    /// Clear screen
-    Clear,
-    /// Blink off
-    BlinkOff,
-    /// Blink black
-    BlinkBlack,
-    /// Blink white
-    BlinkWhite,
-    /// Inverted colours
-    InvertOn,
-    /// Normal colours
-    InvertOff,
+/// Driver for memory in pixel displays described by a [`Panel`]
+///
+/// The frame buffer storage `S` is owned by default, see [`FrameBuffer`] for
+/// using caller provided storage.
+pub struct MipDisplay<SPI: Instance, DISP, P: Panel, S = <P as Panel>::FrameBuffer> {
+    stream: LineStream<SPI, DISP, P>,
+    frame_buffer: FrameBuffer<P, S>, // buffer holding all pixels
 }
 
-impl From<Command> for u8 {
-    fn from(value: Command) -> u8 {
-        use Command::*;
-        match value {
-            DrawLines3bit => 0b_1000_0000,
-            DrawLines1bit => 0b_1000_1000,
-            DrawLines4bit => 0b_1001_0000,
-            NoUpdate => 0b_0000_0000,
-            Clear => 0b_0010_0000,
-            BlinkOff => 0b_0000_0000,
-            BlinkBlack => 0b_0001_0000,
-            BlinkWhite => 0b_0001_1000,
-            InvertOn => 0b_0001_0100,
-            InvertOff => 0b_0000_0000,
+/// Driver for the JDI LPM013M126A
+pub type Lpm013m126a<SPI, DISP> = MipDisplay<SPI, DISP, panel::Lpm013m126a>;
+
+impl<SPI, DISP, P> MipDisplay<SPI, DISP, P>
+where
+    SPI: Instance,
+    DISP: OutputPin,
+    P: Panel,
+{
+    /// Driver sending each line from the `'static` `line_buffer`
+    pub fn new(spi: Spim<SPI>, display: DISP, line_buffer: &'static mut P::LineBuffer) -> Self {
+        Self {
+            stream: LineStream::new(spi, display, line_buffer),
+            frame_buffer: FrameBuffer::new(),
         }
     }
 }
 
-pub struct Lpm013m126a<SPI, DISP> {
-    spi: Spim<SPI>,
-    display: DISP,
-    buffer: [u8; OCTETS_4BIT_LINE_CMD], // buffer holding up to one line of 4-bit pixels
-    frame_buffer: [u8; FRAME_BUFFER_SIZE], // buffer holding up to one line of 4-bit pixels
-    flags: u32,
-    current_line: u8,
-}
-
-pub const DISPLAY_WIDTH: u8 = 176;
-pub const DISPLAY_HEIGHT: u8 = 176;
-const LINE_WIDTH_4BIT: usize = (DISPLAY_WIDTH as usize) >> 1;
-// octets needed for a 4-bit colour line update
-const OCTETS_4BIT_LINE_CMD: usize = LINE_WIDTH_4BIT + 4;
-const FRAME_BUFFER_SIZE: usize = LINE_WIDTH_4BIT * (DISPLAY_HEIGHT as usize);
-
-const FLAGS_NONE: u32 = 0x0000_0000;
-const FLAGS_DRAWING: u32 = 0x0000_0001;
-const FLAGS_UPDATE: u32 = 0x0000_0002;
-
-impl<SPI, DISP> Lpm013m126a<SPI, DISP>
+impl<SPI, DISP, P, S> MipDisplay<SPI, DISP, P, S>
 where
     SPI: Instance,
     DISP: OutputPin,
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
 {
-    pub fn new(spi: Spim<SPI>, display: DISP) -> Self {
-        Self {
-            spi,
-            display,
-            buffer: [0u8; OCTETS_4BIT_LINE_CMD],
-            frame_buffer: [0u8; FRAME_BUFFER_SIZE],
-            flags: FLAGS_NONE,
-            current_line: 0,
-        }
+    /// Driver using caller provided frame buffer storage
+    ///
+    /// With storage of `P::COMMAND_FRAME_OCTETS` the lines are sent from the
+    /// stored line commands, without copying.
+    pub fn with_frame_buffer(
+        spi: Spim<SPI>,
+        display: DISP,
+        line_buffer: &'static mut P::LineBuffer,
+        storage: S,
+    ) -> Result<Self, Error>
+    where
+        S: StaticStorage,
+    {
+        Ok(Self {
+            stream: LineStream::new(spi, display, line_buffer),
+            frame_buffer: FrameBuffer::with_storage(storage)?,
+        })
     }
 
-    pub fn release(self) -> (Spim<SPI>, DISP) {
-        (self.spi, self.display)
+    /// Release the peripherals and the line buffer, once the frame is sent
+    pub fn release(mut self) -> (Spim<SPI>, DISP, &'static mut P::LineBuffer) {
+        self.stream.release(&mut self.frame_buffer)
+    }
+
+    /// Release the peripherals, the line buffer and the frame buffer
+    /// storage, once the frame is sent
+    pub fn release_frame_buffer(mut self) -> (Spim<SPI>, DISP, &'static mut P::LineBuffer, S) {
+        let (spi, display, line_buffer) = self.stream.release(&mut self.frame_buffer);
+        (spi, display, line_buffer, self.frame_buffer.release())
     }
 
     pub fn init(&mut self) -> Result<(), Error> {
-        self.display.set_low().map_err(|_| Error::DisplayError)?;
-        self.display.set_high().map_err(|_| Error::DisplayError)?;
-        Ok(())
+        self.stream.init()
     }
 
+    /// Handle the SPI end of transfer event, starting the next line
     pub fn spi_task_event(&mut self) {
-        self.spi.clear_write_event();
-        self.current_line += 1;
-        if self.current_line < DISPLAY_WIDTH as u8 {
-            let _ = self.send_line(self.current_line);
-        } else {
-            self.current_line = 0;
-            let mut remove_flags = FLAGS_UPDATE;
-            if (self.flags & FLAGS_UPDATE) == FLAGS_UPDATE {
-                let _ = self.send_line(self.current_line);
-            } else {
-                remove_flags |= FLAGS_DRAWING;
-            }
-            self.flags &= !remove_flags;
-        }
+        self.stream.spi_task_event(&mut self.frame_buffer);
     }
 
-    fn send(&mut self, data: &[u8]) -> Result<(), Error> {
-        self.spi
-            .start_spi_dma_transfer(DmaSlice::from_slice(data), DmaSlice::null())
-            .map_err(|_| Error::BusWriteError)
+    /// Send the rest of the frame or command without the SPI interrupt
+    ///
+    /// Transfers continue after returning from `update_display()`, the line
+    /// being sent is owned by the transfer so the driver can be moved. A
+    /// dropped driver finishes the line being sent, not the frame.
+    pub fn flush(&mut self) {
+        self.stream.flush(&mut self.frame_buffer);
     }
 
-    fn send_buffer(&mut self, size: usize) -> Result<(), Error> {
-        self.spi
-            .start_spi_dma_transfer(DmaSlice::from_slice(&self.buffer[..size]), DmaSlice::null())
-            .map_err(|_| Error::BusWriteError)
+    /// Clear the display memory, fails with `BusyError` while a frame is
+    /// sent or while held
+    ///
+    /// Every line is sent with the next update.
+    pub fn send_clear(&mut self) -> Result<(), Error> {
+        self.stream.send_command(Command::Clear)?;
+        self.frame_buffer.mark_all_changed();
+        Ok(())
     }
 
-    fn send_short_command(&mut self, command: Command) -> Result<(), Error> {
-        let cmd = [u8::from(command), 0];
-        self.send(&cmd)
+    pub fn blink_white(&mut self) -> Result<(), Error> {
+        self.stream.send_command(Command::BlinkWhite)
     }
 
-    pub fn send_clear(&mut self) -> Result<(), Error> {
-        self.send_short_command(Command::Clear)
+    /// The frame buffer drawn to
+    pub fn frame_buffer(&self) -> &FrameBuffer<P, S> {
+        &self.frame_buffer
     }
 
-    pub fn blink_white(&mut self) -> Result<(), Error> {
-        self.send_short_command(Command::BlinkWhite)
-    }
-
-    fn send_line(&mut self, line: u8) -> Result<(), Error> {
-        let fb_start = line as usize * LINE_WIDTH_4BIT;
-        let fb_end = (line as usize + 1) * LINE_WIDTH_4BIT;
-        let slice = &self.frame_buffer[fb_start..fb_end];
-        self.buffer[0] = u8::from(Command::DrawLines4bit);
-        self.buffer[1] = line;
-        self.buffer[2..2 + LINE_WIDTH_4BIT].copy_from_slice(slice);
-        self.buffer[2 + LINE_WIDTH_4BIT] = 0;
-        self.buffer[2 + LINE_WIDTH_4BIT + 1] = 0;
-        self.send_buffer(OCTETS_4BIT_LINE_CMD)?;
-        Ok(())
+    pub fn frame_buffer_mut(&mut self) -> &mut FrameBuffer<P, S> {
+        &mut self.frame_buffer
+    }
+
+    /// Set the orientation used when drawing
+    ///
+    /// Already drawn content is not moved.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.frame_buffer.set_orientation(orientation);
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.frame_buffer.orientation()
+    }
+
+    /// Width of the display in the current orientation
+    pub fn width(&self) -> u16 {
+        self.frame_buffer.width()
+    }
+
+    /// Height of the display in the current orientation
+    pub fn height(&self) -> u16 {
+        self.frame_buffer.height()
+    }
+
+    /// Set a pixel, the coordinates are in the current orientation
+    pub fn set_pixel(&mut self, x: u16, y: u16, colour: Palette8) {
+        self.frame_buffer.set_pixel(x, y, colour);
+    }
+
+    /// Fill the whole frame buffer with a single colour
+    pub fn fill(&mut self, colour: Palette8) {
+        self.frame_buffer.fill(colour);
     }
 
-    pub fn set_pixel(&mut self, x: u8, y: u8, colour: Palette8) {
-        let c = u8::from(colour);
-        let (c, mask) = if x & 1 == 1 {
-            (c << 4, 0x0f)
-        } else {
-            (c, 0xf0)
-        };
-        let x = (x >> 1) as usize;
-        let y = y as usize * LINE_WIDTH_4BIT;
-        let i = x + y;
-        self.frame_buffer[i] = (self.frame_buffer[i] & mask) | c;
+    /// Fill a rectangle with a single colour, the coordinates are in the
+    /// current orientation
+    ///
+    /// The rectangle is clipped to the display.
+    pub fn fill_rect(&mut self, x: u16, y: u16, width: u16, height: u16, colour: Palette8) {
+        self.frame_buffer.fill_rect(x, y, width, height, colour);
     }
 
+    /// Is a frame being sent to the display?
+    pub fn is_drawing(&self) -> bool {
+        self.stream.is_drawing()
+    }
+
+    /// Exchange the frame buffer sent to the display
+    pub(crate) fn swap_frame_buffer(&mut self, frame_buffer: &mut FrameBuffer<P, S>) {
+        core::mem::swap(&mut self.frame_buffer, frame_buffer);
+    }
+
+    /// Send the lines of the frame buffer changed since they were sent
+    ///
+    /// While held, the frame is sent when resumed.
     pub fn update_display(&mut self) -> Result<(), Error> {
-        self.flags |= FLAGS_UPDATE;
-        if (self.flags & FLAGS_DRAWING) == 0 {
-            self.flags |= FLAGS_DRAWING;
-            self.send_line(0)
-        } else {
-            Ok(())
-        }
+        self.stream.update_display(&mut self.frame_buffer)
+    }
+
+    /// Stop sending lines and disable the SPIM, the display keeps its image
+    ///
+    /// A frame or command being sent is completed first. VCOM must keep
+    /// toggling while held.
+    pub fn hold(&mut self) {
+        self.stream.hold();
+    }
+
+    /// Is the display held, with the SPIM disabled?
+    pub fn is_held(&self) -> bool {
+        self.stream.is_held()
+    }
+
+    /// Enable the SPIM and send any update requested while held
+    pub fn resume(&mut self) -> Result<(), Error> {
+        self.stream.resume(&mut self.frame_buffer)
+    }
+
+    /// Timing of the sent frames
+    pub fn stats(&self) -> &FrameStats {
+        self.stream.stats()
+    }
+
+    pub(crate) fn stats_mut(&mut self) -> &mut FrameStats {
+        self.stream.stats_mut()
+    }
+
+    /// Clock used to time frames, see [`FrameStats`]
+    pub fn set_clock(&mut self, clock: fn() -> u32, ticks_per_second: u32) {
+        self.stream.stats_mut().set_clock(clock, ticks_per_second);
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stream.stats_mut().reset();
+    }
+
+    /// Log at most `lines` lines of the frame buffer for the `screenshot`
+    /// example, returns `true` once the screenshot is complete
+    ///
+    /// Logging takes a while, log a few lines at a time to keep drawing and
+    /// sending in between. The screenshot may then show parts of two frames.
+    pub fn log_screenshot(&self, capture: &mut screenshot::Capture, lines: u16) -> bool {
+        screenshot::log(capture, &self.frame_buffer, lines)
+    }
+
+    /// Clear the display memory, set DISP low and disable the SPIM
+    ///
+    /// Stop VCOM after this, before removing the panel supply. Fails with
+    /// `BusyError` while a frame is sent.
+    pub fn power_off(&mut self) -> Result<(), Error> {
+        self.stream.power_off()?;
+        self.frame_buffer.mark_all_changed();
+        Ok(())
+    }
+
+    /// Set DISP high after `power_off` and send the frame buffer
+    ///
+    /// VCOM must be toggling before the display is powered on.
+    pub fn power_on(&mut self) -> Result<(), Error> {
+        self.stream.power_on(&mut self.frame_buffer)
     }
 }
 
 #[cfg(feature = "graphics")]
-impl<SPI, DISP> DrawTarget for Lpm013m126a<SPI, DISP>
+impl<SPI, DISP, P, S> DrawTarget for MipDisplay<SPI, DISP, P, S>
 where
     SPI: Instance,
     DISP: OutputPin,
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
 {
     type Color = Palette8;
-    // `ExampleDisplay` uses a framebuffer and doesn't need to communicate with the display
-    // controller to draw pixel, which means that drawing operations can never fail. To reflect
-    // this the type `Infallible` was chosen as the `Error` type.
     type Error = core::convert::Infallible;
 
     fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
-        const X_LIMIT: i32 = DISPLAY_WIDTH as i32 - 1;
-        const Y_LIMIT: i32 = DISPLAY_HEIGHT as i32 - 1;
-        for Pixel(coord, colour) in pixels.into_iter() {
-            // Check if the pixel coordinates are out of bounds (negative or greater than
-            // (63,63)). `DrawTarget` implementation are required to discard any out of bounds
-            // pixels without returning an error or causing a panic.
-            if let Ok((x @ 0..=X_LIMIT, y @ 0..=Y_LIMIT)) = coord.try_into() {
-                self.set_pixel(x as u8, y as u8, colour);
-            }
-        }
+        self.frame_buffer.draw_iter(pixels)
+    }
 
-        Ok(())
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colours: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.frame_buffer.fill_contiguous(area, colours)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, colour: Self::Color) -> Result<(), Self::Error> {
+        self.frame_buffer.fill_solid(area, colour)
+    }
+
+    fn clear(&mut self, colour: Self::Color) -> Result<(), Self::Error> {
+        self.frame_buffer.clear(colour)
     }
 }
 
 #[cfg(feature = "graphics")]
-impl<SPI, DISP> OriginDimensions for Lpm013m126a<SPI, DISP> {
+impl<SPI, DISP, P, S> OriginDimensions for MipDisplay<SPI, DISP, P, S>
+where
+    SPI: Instance,
+    P: Panel,
+{
     fn size(&self) -> Size {
-        Size::new(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32)
+        self.frame_buffer.size()
     }
 }
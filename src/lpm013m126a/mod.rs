@@ -1,5 +1,77 @@
+#[cfg(feature = "graphics")]
+pub mod chart;
+#[cfg(feature = "graphics")]
+pub mod clock_face;
+pub mod command;
+#[cfg(feature = "graphics")]
+pub mod console;
+pub mod decoder;
+#[cfg(feature = "graphics")]
+pub mod dither;
+#[cfg(feature = "hardware")]
+pub mod double_buffer;
+pub mod frame_buffer;
+pub mod image;
+#[cfg(feature = "hardware")]
+pub mod line_display;
+#[cfg(feature = "hardware")]
+mod line_stream;
+#[cfg(feature = "hardware")]
 pub mod lpm013m126a;
+pub mod orientation;
 pub mod palette;
+pub mod panel;
+#[cfg(feature = "graphics")]
+pub mod pattern;
+pub mod qr;
+pub mod screenshot;
+#[cfg(feature = "std")]
+pub mod simulator;
+pub mod sprite;
+pub mod stats;
+#[cfg(feature = "graphics")]
+pub mod text;
+#[cfg(feature = "graphics")]
+pub mod widget;
 
-pub use lpm013m126a::{Lpm013m126a, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+#[cfg(feature = "graphics")]
+pub use chart::Chart;
+#[cfg(feature = "graphics")]
+pub use clock_face::{AnalogClock, DigitalClock};
+pub use command::Command;
+#[cfg(feature = "graphics")]
+pub use console::Console;
+pub use decoder::Decoder;
+#[cfg(feature = "graphics")]
+pub use dither::{Dither, Dithering};
+#[cfg(feature = "hardware")]
+pub use double_buffer::DoubleBuffered;
+pub use frame_buffer::{FrameBuffer, StaticStorage};
+pub use image::RleImage;
+#[cfg(feature = "hardware")]
+pub use line_display::{Line, LineDisplay, LineRenderer};
+#[cfg(feature = "hardware")]
+pub use lpm013m126a::{Lpm013m126a, MipDisplay};
+pub use orientation::{Orientation, Rotation};
 pub use palette::Palette8;
+pub use panel::Panel;
+#[cfg(feature = "graphics")]
+pub use pattern::{Pattern, Patterned};
+pub use qr::QrCode;
+#[cfg(feature = "graphics")]
+pub use qr::QrView;
+pub use sprite::{Scene, Sprite, TileSheet};
+pub use stats::FrameStats;
+#[cfg(feature = "graphics")]
+pub use text::{Alignment, Font, TextBox, TextStyle};
+#[cfg(feature = "graphics")]
+pub use widget::{Theme, Widget};
+
+pub const DISPLAY_WIDTH: u8 = 176;
+pub const DISPLAY_HEIGHT: u8 = 176;
+const _: () = assert!(
+    DISPLAY_WIDTH as u16 == <panel::Lpm013m126a as Panel>::WIDTH
+        && DISPLAY_HEIGHT as u16 == <panel::Lpm013m126a as Panel>::HEIGHT
+);
+/// Octets of the `'static` line buffer of the LPM013M126A drivers
+pub const LINE_BUFFER_OCTETS: usize = <panel::Lpm013m126a as Panel>::LINE_COMMAND_OCTETS;
@@ -0,0 +1,86 @@
+//! # Display orientation
+//!
+//! Maps coordinates as seen by the application onto the coordinates of the
+//! physical display. Mirroring is applied before the rotation.
+
+/// Clockwise rotation of the displayed image
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rotation {
+    Rotate0,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+/// Rotation and mirroring of the displayed image
+#[derive(Clone, Copy, PartialEq)]
+pub struct Orientation {
+    pub rotation: Rotation,
+    /// Mirror the image along the X axis, left becomes right
+    pub mirror_x: bool,
+    /// Mirror the image along the Y axis, top becomes bottom
+    pub mirror_y: bool,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Self::new(Rotation::Rotate0)
+    }
+}
+
+impl Orientation {
+    /// Orientation with the given rotation and no mirroring
+    pub const fn new(rotation: Rotation) -> Self {
+        Self {
+            rotation,
+            mirror_x: false,
+            mirror_y: false,
+        }
+    }
+
+    /// Orientation with the given rotation and mirroring
+    pub const fn with_mirror(rotation: Rotation, mirror_x: bool, mirror_y: bool) -> Self {
+        Self {
+            rotation,
+            mirror_x,
+            mirror_y,
+        }
+    }
+
+    /// Is the orientation the native orientation of the display?
+    pub fn is_identity(&self) -> bool {
+        self.rotation == Rotation::Rotate0 && !self.mirror_x && !self.mirror_y
+    }
+
+    /// Are the X and Y axis swapped?
+    pub fn swaps_axes(&self) -> bool {
+        matches!(self.rotation, Rotation::Rotate90 | Rotation::Rotate270)
+    }
+
+    /// Size of the display as seen by the application, given the size of
+    /// the display
+    pub fn size(&self, width: u16, height: u16) -> (u16, u16) {
+        if self.swaps_axes() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Transform application coordinates into display coordinates, given the
+    /// size of the display
+    ///
+    /// The coordinates must be within the size of the display as seen by the
+    /// application.
+    pub fn transform(&self, x: u16, y: u16, width: u16, height: u16) -> (u16, u16) {
+        let (w, h) = self.size(width, height);
+        let x = if self.mirror_x { w - 1 - x } else { x };
+        let y = if self.mirror_y { h - 1 - y } else { y };
+        match self.rotation {
+            Rotation::Rotate0 => (x, y),
+            Rotation::Rotate90 => (width - 1 - y, x),
+            Rotation::Rotate180 => (width - 1 - x, height - 1 - y),
+            Rotation::Rotate270 => (y, height - 1 - x),
+        }
+    }
+}
@@ -10,9 +10,21 @@
 //! * Pink
 //! * Yellow
 //! * White
+//!
+//! A colour has two representations, the index 0 to 7 used by `u8` and
+//! `RawU4` conversions and the nibble sent to the display.
+//! The index has red in bit 2, green in bit 1 and blue in bit 0, the display
+//! nibble is the index shifted left by one.
+//!
+//! As the raw representation is the index, images stored as 4-bit indices
+//! can be drawn using `ImageRaw<Palette8>`. True colour images, such as BMP
+//! and TGA files, are converted to the nearest colour.
 
 #[cfg(feature = "graphics")]
-use embedded_graphics::pixelcolor::{raw::RawU4, PixelColor};
+use embedded_graphics::pixelcolor::{
+    raw::{RawData, RawU4},
+    BinaryColor, Gray8, GrayColor, PixelColor, Rgb555, Rgb565, Rgb888, RgbColor,
+};
 
 // impl private::Sealed for RawU3 {}
 
@@ -29,22 +41,24 @@ pub enum Palette8 {
 }
 
 impl From<Palette8> for u8 {
+    /// Colour index
     fn from(value: Palette8) -> u8 {
         use Palette8::*;
         match value {
-            Black => 0b_0000_0000,
-            Blue => 0b_0000_0010,
-            Green => 0b_0000_0100,
-            Cyan => 0b_0000_0110,
-            Red => 0b_0000_1000,
-            Pink => 0b_0000_1010,
-            Yellow => 0b_0000_1100,
-            White => 0b_0000_1110,
+            Black => 0,
+            Blue => 1,
+            Green => 2,
+            Cyan => 3,
+            Red => 4,
+            Pink => 5,
+            Yellow => 6,
+            White => 7,
         }
     }
 }
 
 impl From<u8> for Palette8 {
+    /// Colour from index, indices above 7 are black
     fn from(value: u8) -> Palette8 {
         use Palette8::*;
         match value {
@@ -65,9 +79,107 @@ impl PixelColor for Palette8 {
     type Raw = RawU4;
 }
 
+#[cfg(feature = "graphics")]
+impl From<RawU4> for Palette8 {
+    fn from(value: RawU4) -> Palette8 {
+        Palette8::from(value.into_inner())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<Palette8> for RawU4 {
+    fn from(value: Palette8) -> RawU4 {
+        RawU4::new(u8::from(value))
+    }
+}
+
 impl Palette8 {
     /// Converts two colors into a single byte for the Display
     pub fn colors_byte(a: Palette8, b: Palette8) -> u8 {
-        u8::from(a) << 4 | u8::from(b)
+        a.nibble() << 4 | b.nibble()
+    }
+
+    /// The 4-bit value sent to the display, RGB in bit 3 to 1
+    pub fn nibble(self) -> u8 {
+        u8::from(self) << 1
+    }
+
+    /// Colour from the 4-bit value sent to the display, bit 0 is ignored
+    pub fn from_nibble(value: u8) -> Palette8 {
+        Palette8::from((value >> 1) & 0b111)
+    }
+
+    /// Colour from the state of the red, green and blue channels
+    pub fn from_rgb(red: bool, green: bool, blue: bool) -> Palette8 {
+        Palette8::from((red as u8) << 2 | (green as u8) << 1 | blue as u8)
+    }
+
+    /// The colour with all channels inverted
+    pub fn inverted(self) -> Palette8 {
+        Palette8::from(7 - u8::from(self))
+    }
+
+    /// The state of the red, green and blue channels
+    pub fn rgb(self) -> (bool, bool, bool) {
+        let c = u8::from(self);
+        (c & 0b100 != 0, c & 0b010 != 0, c & 0b001 != 0)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<Rgb888> for Palette8 {
+    /// Nearest colour, each channel is thresholded at half intensity
+    fn from(value: Rgb888) -> Palette8 {
+        Palette8::from(microbit_two_convert::nearest([
+            value.r(),
+            value.g(),
+            value.b(),
+        ]))
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<Rgb565> for Palette8 {
+    /// Nearest colour, each channel is thresholded at half intensity
+    fn from(value: Rgb565) -> Palette8 {
+        Palette8::from_rgb(value.r() >= 0x10, value.g() >= 0x20, value.b() >= 0x10)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<Rgb555> for Palette8 {
+    /// Nearest colour, each channel is thresholded at half intensity
+    fn from(value: Rgb555) -> Palette8 {
+        Palette8::from_rgb(value.r() >= 0x10, value.g() >= 0x10, value.b() >= 0x10)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<Gray8> for Palette8 {
+    /// Black or white, thresholded at half intensity
+    fn from(value: Gray8) -> Palette8 {
+        if value.luma() >= 0x80 {
+            Palette8::White
+        } else {
+            Palette8::Black
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<BinaryColor> for Palette8 {
+    fn from(value: BinaryColor) -> Palette8 {
+        match value {
+            BinaryColor::Off => Palette8::Black,
+            BinaryColor::On => Palette8::White,
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl From<Palette8> for Rgb888 {
+    fn from(value: Palette8) -> Rgb888 {
+        let (r, g, b) = value.rgb();
+        Rgb888::new(r as u8 * 0xff, g as u8 * 0xff, b as u8 * 0xff)
     }
 }
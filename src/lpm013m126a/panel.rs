@@ -0,0 +1,353 @@
+//! # Memory in pixel panel descriptors
+//!
+//! Describes the size, colour depth and command encoding of the JDI and Sharp
+//! memory in pixel displays supported by [`MipDisplay`].
+//!
+//! Each line is transferred as two octets of header, the pixel data and two
+//! trailing octets. The JDI panels are driven in 4-bit colour mode, two pixels
+//! per octet with the first pixel in the lower nibble. The Sharp panels are
+//! monochrome, eight pixels per octet with the first pixel in the most
+//! significant bit.
+//!
+//! [`MipDisplay`]: crate::lpm013m126a::MipDisplay
+
+use crate::lpm013m126a::{Command, Palette8};
+
+/// Description of a memory in pixel panel
+pub trait Panel {
+    /// Width in pixels
+    const WIDTH: u16;
+    /// Height in pixels
+    const HEIGHT: u16;
+    /// Bits per pixel in the frame buffer, either 1 or 4
+    const BITS_PER_PIXEL: u8;
+    /// Octets of pixel data per line
+    const LINE_OCTETS: usize = Self::WIDTH as usize * Self::BITS_PER_PIXEL as usize / 8;
+    /// Octets of a complete line transfer, header, pixel data and trailer
+    const LINE_COMMAND_OCTETS: usize = Self::LINE_OCTETS + 4;
+    /// Octets of a frame buffer holding only pixel data
+    const FRAME_OCTETS: usize = Self::LINE_OCTETS * Self::HEIGHT as usize;
+    /// Octets of a frame buffer holding a line update command per line
+    const COMMAND_FRAME_OCTETS: usize = Self::LINE_COMMAND_OCTETS * Self::HEIGHT as usize;
+
+    /// Storage for the frame buffer, `FRAME_OCTETS` octets
+    type FrameBuffer: AsRef<[u8]> + AsMut<[u8]>;
+    /// Storage for a line transfer, `LINE_COMMAND_OCTETS` octets
+    type LineBuffer: AsRef<[u8]> + AsMut<[u8]> + 'static;
+    /// A bit per line, `HEIGHT` bits rounded up to whole words
+    type LineBits: AsRef<[u32]> + AsMut<[u32]>;
+
+    fn frame_buffer() -> Self::FrameBuffer;
+
+    fn line_buffer() -> Self::LineBuffer;
+
+    fn line_bits() -> Self::LineBits;
+
+    /// Header octets preceding the pixel data of `line`
+    fn line_header(line: u16) -> [u8; 2];
+
+    /// Encoding of a command without data, `None` if unsupported by the panel
+    fn command(command: Command) -> Option<u8>;
+
+    /// Line of a line transfer header, `None` if not a line update
+    fn decode_line_header(header: [u8; 2]) -> Option<u16>;
+
+    /// Command of a command octet, `None` if unknown to the panel
+    ///
+    /// Commands sharing an encoding are decoded as `NoUpdate`.
+    fn decode_command(octet: u8) -> Option<Command>;
+
+    /// Frame buffer value of a colour, `BITS_PER_PIXEL` wide
+    fn pixel(colour: Palette8) -> u8;
+
+    /// Colour shown for a frame buffer value
+    fn colour(pixel: u8) -> Palette8;
+}
+
+/// Is the storage of a panel the size given by its dimensions?
+const fn storage_matches<P: Panel>() -> bool {
+    core::mem::size_of::<P::FrameBuffer>() == P::FRAME_OCTETS
+        && core::mem::size_of::<P::LineBuffer>() == P::LINE_COMMAND_OCTETS
+        && core::mem::size_of::<P::LineBits>() == (P::HEIGHT as usize + 31) / 32 * 4
+}
+
+// The storage types are written out per panel, check them when building
+const _: () = assert!(storage_matches::<Lpm013m126a>());
+const _: () = assert!(storage_matches::<Lpm027m128b>());
+const _: () = assert!(storage_matches::<Ls013b7dh03>());
+const _: () = assert!(storage_matches::<Ls027b7dh01>());
+
+/// JDI 8 colour panels
+fn jdi_line_header(line: u16) -> [u8; 2] {
+    [
+        u8::from(Command::DrawLines4bit) | (line >> 8) as u8,
+        line as u8,
+    ]
+}
+
+fn jdi_decode_line_header(header: [u8; 2]) -> Option<u16> {
+    if header[0] & 0b_1111_1100 == u8::from(Command::DrawLines4bit) {
+        Some(((header[0] & 0b11) as u16) << 8 | header[1] as u16)
+    } else {
+        None
+    }
+}
+
+fn jdi_decode_command(octet: u8) -> Option<Command> {
+    [
+        Command::NoUpdate,
+        Command::Clear,
+        Command::BlinkBlack,
+        Command::BlinkWhite,
+        Command::InvertOn,
+    ]
+    .iter()
+    .copied()
+    .find(|&command| u8::from(command) == octet)
+}
+
+/// Sharp monochrome panels, line addresses start at 1 and are sent least
+/// significant bit first. VCOM is expected to be driven by the EXTCOMIN pin.
+fn sharp_line_header(line: u16) -> [u8; 2] {
+    [SHARP_WRITE_LINE, ((line + 1) as u8).reverse_bits()]
+}
+
+const SHARP_WRITE_LINE: u8 = 0b_1000_0000;
+// Software VCOM, ignored when decoding
+const SHARP_VCOM: u8 = 0b_0100_0000;
+
+fn sharp_decode_line_header(header: [u8; 2]) -> Option<u16> {
+    let address = header[1].reverse_bits();
+    if header[0] & !SHARP_VCOM == SHARP_WRITE_LINE && address > 0 {
+        Some(address as u16 - 1)
+    } else {
+        None
+    }
+}
+
+fn sharp_decode_command(octet: u8) -> Option<Command> {
+    match octet & !SHARP_VCOM {
+        0b_0000_0000 => Some(Command::NoUpdate),
+        0b_0010_0000 => Some(Command::Clear),
+        _ => None,
+    }
+}
+
+fn sharp_command(command: Command) -> Option<u8> {
+    match command {
+        Command::NoUpdate | Command::Clear => Some(u8::from(command)),
+        _ => None,
+    }
+}
+
+/// A pixel is white if the colour is closer to white than black
+fn monochrome_pixel(colour: Palette8) -> u8 {
+    match colour {
+        Palette8::Green | Palette8::Cyan | Palette8::Yellow | Palette8::White => 1,
+        _ => 0,
+    }
+}
+
+fn monochrome_colour(pixel: u8) -> Palette8 {
+    if pixel & 1 == 1 {
+        Palette8::White
+    } else {
+        Palette8::Black
+    }
+}
+
+/// JDI LPM013M126A, 1.28" 176x176 8 colours
+pub struct Lpm013m126a;
+
+impl Panel for Lpm013m126a {
+    const WIDTH: u16 = 176;
+    const HEIGHT: u16 = 176;
+    const BITS_PER_PIXEL: u8 = 4;
+
+    type FrameBuffer = [u8; 88 * 176];
+    type LineBuffer = [u8; 88 + 4];
+    type LineBits = [u32; 6];
+
+    fn frame_buffer() -> Self::FrameBuffer {
+        [0u8; 88 * 176]
+    }
+
+    fn line_buffer() -> Self::LineBuffer {
+        [0u8; 88 + 4]
+    }
+
+    fn line_bits() -> Self::LineBits {
+        [0u32; 6]
+    }
+
+    fn line_header(line: u16) -> [u8; 2] {
+        jdi_line_header(line)
+    }
+
+    fn command(command: Command) -> Option<u8> {
+        Some(u8::from(command))
+    }
+
+    fn decode_line_header(header: [u8; 2]) -> Option<u16> {
+        jdi_decode_line_header(header)
+    }
+
+    fn decode_command(octet: u8) -> Option<Command> {
+        jdi_decode_command(octet)
+    }
+
+    fn pixel(colour: Palette8) -> u8 {
+        colour.nibble()
+    }
+
+    fn colour(pixel: u8) -> Palette8 {
+        Palette8::from_nibble(pixel)
+    }
+}
+
+/// JDI LPM027M128B, 2.7" 400x240 8 colours
+pub struct Lpm027m128b;
+
+impl Panel for Lpm027m128b {
+    const WIDTH: u16 = 400;
+    const HEIGHT: u16 = 240;
+    const BITS_PER_PIXEL: u8 = 4;
+
+    type FrameBuffer = [u8; 200 * 240];
+    type LineBuffer = [u8; 200 + 4];
+    type LineBits = [u32; 8];
+
+    fn frame_buffer() -> Self::FrameBuffer {
+        [0u8; 200 * 240]
+    }
+
+    fn line_buffer() -> Self::LineBuffer {
+        [0u8; 200 + 4]
+    }
+
+    fn line_bits() -> Self::LineBits {
+        [0u32; 8]
+    }
+
+    fn line_header(line: u16) -> [u8; 2] {
+        jdi_line_header(line)
+    }
+
+    fn command(command: Command) -> Option<u8> {
+        Some(u8::from(command))
+    }
+
+    fn decode_line_header(header: [u8; 2]) -> Option<u16> {
+        jdi_decode_line_header(header)
+    }
+
+    fn decode_command(octet: u8) -> Option<Command> {
+        jdi_decode_command(octet)
+    }
+
+    fn pixel(colour: Palette8) -> u8 {
+        colour.nibble()
+    }
+
+    fn colour(pixel: u8) -> Palette8 {
+        Palette8::from_nibble(pixel)
+    }
+}
+
+/// Sharp LS013B7DH03, 1.28" 128x128 monochrome
+pub struct Ls013b7dh03;
+
+impl Panel for Ls013b7dh03 {
+    const WIDTH: u16 = 128;
+    const HEIGHT: u16 = 128;
+    const BITS_PER_PIXEL: u8 = 1;
+
+    type FrameBuffer = [u8; 16 * 128];
+    type LineBuffer = [u8; 16 + 4];
+    type LineBits = [u32; 4];
+
+    fn frame_buffer() -> Self::FrameBuffer {
+        [0u8; 16 * 128]
+    }
+
+    fn line_buffer() -> Self::LineBuffer {
+        [0u8; 16 + 4]
+    }
+
+    fn line_bits() -> Self::LineBits {
+        [0u32; 4]
+    }
+
+    fn line_header(line: u16) -> [u8; 2] {
+        sharp_line_header(line)
+    }
+
+    fn command(command: Command) -> Option<u8> {
+        sharp_command(command)
+    }
+
+    fn decode_line_header(header: [u8; 2]) -> Option<u16> {
+        sharp_decode_line_header(header)
+    }
+
+    fn decode_command(octet: u8) -> Option<Command> {
+        sharp_decode_command(octet)
+    }
+
+    fn pixel(colour: Palette8) -> u8 {
+        monochrome_pixel(colour)
+    }
+
+    fn colour(pixel: u8) -> Palette8 {
+        monochrome_colour(pixel)
+    }
+}
+
+/// Sharp LS027B7DH01, 2.7" 400x240 monochrome
+pub struct Ls027b7dh01;
+
+impl Panel for Ls027b7dh01 {
+    const WIDTH: u16 = 400;
+    const HEIGHT: u16 = 240;
+    const BITS_PER_PIXEL: u8 = 1;
+
+    type FrameBuffer = [u8; 50 * 240];
+    type LineBuffer = [u8; 50 + 4];
+    type LineBits = [u32; 8];
+
+    fn frame_buffer() -> Self::FrameBuffer {
+        [0u8; 50 * 240]
+    }
+
+    fn line_buffer() -> Self::LineBuffer {
+        [0u8; 50 + 4]
+    }
+
+    fn line_bits() -> Self::LineBits {
+        [0u32; 8]
+    }
+
+    fn line_header(line: u16) -> [u8; 2] {
+        sharp_line_header(line)
+    }
+
+    fn command(command: Command) -> Option<u8> {
+        sharp_command(command)
+    }
+
+    fn decode_line_header(header: [u8; 2]) -> Option<u16> {
+        sharp_decode_line_header(header)
+    }
+
+    fn decode_command(octet: u8) -> Option<Command> {
+        sharp_decode_command(octet)
+    }
+
+    fn pixel(colour: Palette8) -> u8 {
+        monochrome_pixel(colour)
+    }
+
+    fn colour(pixel: u8) -> Palette8 {
+        monochrome_colour(pixel)
+    }
+}
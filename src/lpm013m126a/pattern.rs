@@ -0,0 +1,225 @@
+//! # Pattern fills
+//!
+//! A [`Pattern`] mixes two `Palette8` colours in a checkerboard, stripes or
+//! an ordered dither at a ratio in sixteenths, giving many more shades than
+//! the eight colours of the display. Patterns are used as the colour of
+//! primitives drawn on a [`Patterned`] draw target.
+//!
+//! ```ignore
+//! let grey = Pattern::mix(Palette8::Black, Palette8::White, 8);
+//! Rectangle::new(Point::new(8, 8), Size::new(32, 32))
+//!     .into_styled(PrimitiveStyle::with_fill(grey))
+//!     .draw(&mut Patterned::new(&mut display))?;
+//! ```
+//!
+//! Patterns are aligned to the display coordinates, so adjacent shapes with
+//! the same pattern join without seams.
+
+use crate::lpm013m126a::Palette8;
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, Point},
+    pixelcolor::PixelColor,
+    primitives::{PointsIter, Rectangle},
+    Pixel,
+};
+use microbit_two_convert::BAYER_4X4;
+
+/// Direction of stripes
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    Horizontal,
+    Vertical,
+    /// Falling from left to right
+    Diagonal,
+    /// Rising from left to right
+    AntiDiagonal,
+}
+
+/// Fill pattern of two colours
+#[derive(Clone, Copy, PartialEq)]
+pub enum Pattern {
+    Solid(Palette8),
+    /// Squares of `size` pixels alternating between the colours
+    Checkerboard {
+        first: Palette8,
+        second: Palette8,
+        size: u8,
+    },
+    /// Stripes of `width` pixels of the first colour and `gap` pixels of the
+    /// second colour
+    Stripes {
+        first: Palette8,
+        second: Palette8,
+        direction: Direction,
+        width: u8,
+        gap: u8,
+    },
+    /// Ordered dither with `ratio` sixteenths of the second colour
+    Mix {
+        first: Palette8,
+        second: Palette8,
+        ratio: u8,
+    },
+}
+
+impl Pattern {
+    /// Checkerboard of single pixels
+    pub fn checkerboard(first: Palette8, second: Palette8) -> Self {
+        Pattern::Checkerboard {
+            first,
+            second,
+            size: 1,
+        }
+    }
+
+    /// Hatch of single pixel lines in `direction` spaced `spacing` pixels
+    pub fn hatch(line: Palette8, background: Palette8, direction: Direction, spacing: u8) -> Self {
+        Pattern::Stripes {
+            first: line,
+            second: background,
+            direction,
+            width: 1,
+            gap: spacing.saturating_sub(1),
+        }
+    }
+
+    /// Mix of two colours, `ratio` of 0 gives the first colour and 16 the
+    /// second
+    pub fn mix(first: Palette8, second: Palette8, ratio: u8) -> Self {
+        Pattern::Mix {
+            first,
+            second,
+            ratio: ratio.min(16),
+        }
+    }
+
+    /// Colour of the pattern at a point
+    pub fn colour_at(&self, point: Point) -> Palette8 {
+        match *self {
+            Pattern::Solid(colour) => colour,
+            Pattern::Checkerboard {
+                first,
+                second,
+                size,
+            } => {
+                let size = size.max(1) as i32;
+                if (point.x.div_euclid(size) + point.y.div_euclid(size)) & 1 == 0 {
+                    first
+                } else {
+                    second
+                }
+            }
+            Pattern::Stripes {
+                first,
+                second,
+                direction,
+                width,
+                gap,
+            } => {
+                let position = match direction {
+                    Direction::Horizontal => point.y,
+                    Direction::Vertical => point.x,
+                    Direction::Diagonal => point.x - point.y,
+                    Direction::AntiDiagonal => point.x + point.y,
+                };
+                let period = width as i32 + gap as i32;
+                if period == 0 || position.rem_euclid(period) < width as i32 {
+                    first
+                } else {
+                    second
+                }
+            }
+            Pattern::Mix {
+                first,
+                second,
+                ratio,
+            } => {
+                if BAYER_4X4[(point.y & 3) as usize][(point.x & 3) as usize] < ratio {
+                    second
+                } else {
+                    first
+                }
+            }
+        }
+    }
+}
+
+impl PixelColor for Pattern {
+    type Raw = ();
+}
+
+impl From<Palette8> for Pattern {
+    fn from(colour: Palette8) -> Self {
+        Pattern::Solid(colour)
+    }
+}
+
+/// Draw target adapter drawing `Pattern` colours on a `Palette8` target
+pub struct Patterned<'a, D> {
+    target: &'a mut D,
+}
+
+impl<'a, D> Patterned<'a, D>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    pub fn new(target: &'a mut D) -> Self {
+        Self { target }
+    }
+}
+
+impl<D> Dimensions for Patterned<'_, D>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<D> DrawTarget for Patterned<'_, D>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    type Color = Pattern;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, pattern)| Pixel(point, pattern.colour_at(point))),
+        )
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colours: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.target.fill_contiguous(
+            area,
+            area.points()
+                .zip(colours)
+                .map(|(point, pattern)| pattern.colour_at(point)),
+        )
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, colour: Self::Color) -> Result<(), Self::Error> {
+        match colour {
+            Pattern::Solid(colour) => self.target.fill_solid(area, colour),
+            Pattern::Mix {
+                first, ratio: 0, ..
+            } => self.target.fill_solid(area, first),
+            Pattern::Mix {
+                second, ratio: 16, ..
+            } => self.target.fill_solid(area, second),
+            pattern => self
+                .target
+                .fill_contiguous(area, area.points().map(|point| pattern.colour_at(point))),
+        }
+    }
+}
@@ -0,0 +1,794 @@
+//! # QR codes
+//!
+//! [`QrCode`] encodes text or binary data as a QR code, for showing device
+//! identifiers or pairing URLs which can be scanned with a phone. Byte and
+//! alphanumeric modes are supported, with a selectable error correction
+//! level. The smallest version holding the data is used, up to version 37,
+//! the largest version fitting the display with a quiet zone.
+//!
+//! The encoder does not allocate, the modules are kept in a fixed size
+//! buffer of 3404 octets. Encoding uses another 3196 octets of stack.
+//!
+//! [`QrView`] draws a code with its quiet zone, scaled by an integer factor.
+//!
+//! ```ignore
+//! let code = QrCode::encode_text("MICROBIT-0123", ErrorCorrection::Medium)?;
+//! QrView::fit(&code, display.bounding_box()).draw(&mut display)?;
+//! ```
+
+use crate::Error;
+
+#[cfg(feature = "graphics")]
+use crate::lpm013m126a::Palette8;
+#[cfg(feature = "graphics")]
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    primitives::{PointsIter, Rectangle},
+    Drawable,
+};
+
+/// Largest version supported
+pub const MAX_VERSION: u8 = 37;
+/// Modules of the quiet zone on each side
+pub const QUIET_ZONE: u32 = 4;
+
+const MAX_SIZE: usize = size_of_version(MAX_VERSION) as usize;
+const MODULE_OCTETS: usize = (MAX_SIZE * MAX_SIZE + 7) / 8;
+const MAX_CODEWORDS: usize = raw_data_modules(MAX_VERSION) / 8;
+const MAX_ECC_CODEWORDS: usize = 30;
+
+const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+// Error correction codewords per block, by level and version
+const ECC_CODEWORDS_PER_BLOCK: [[u8; 41]; 4] = [
+    [
+        0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28,
+        30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28,
+        28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    ],
+    [
+        0, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30, 28, 30,
+        30, 30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28, 30, 24,
+        30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+];
+
+// Error correction blocks, by level and version
+const ECC_BLOCKS: [[u8; 41]; 4] = [
+    [
+        0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13,
+        14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+    ],
+    [
+        0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21,
+        23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+    ],
+    [
+        0, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29,
+        34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68,
+    ],
+    [
+        0, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32,
+        35, 37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81,
+    ],
+];
+
+/// Error correction level, the share of codewords which can be restored
+#[derive(Clone, Copy, PartialEq)]
+pub enum ErrorCorrection {
+    /// About 7 %
+    Low,
+    /// About 15 %
+    Medium,
+    /// About 25 %
+    Quartile,
+    /// About 30 %
+    High,
+}
+
+impl ErrorCorrection {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Bits of the format information
+    fn format_bits(self) -> u16 {
+        match self {
+            ErrorCorrection::Low => 1,
+            ErrorCorrection::Medium => 0,
+            ErrorCorrection::Quartile => 3,
+            ErrorCorrection::High => 2,
+        }
+    }
+}
+
+/// Encoding of the data
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Digits, upper case letters, space and `$%*+-./:`, 5.5 bits per
+    /// character
+    Alphanumeric,
+    /// Any octet, 8 bits per octet
+    Byte,
+}
+
+impl Mode {
+    fn indicator(self) -> u32 {
+        match self {
+            Mode::Alphanumeric => 0b0010,
+            Mode::Byte => 0b0100,
+        }
+    }
+
+    /// Bits of the character count
+    fn count_bits(self, version: u8) -> usize {
+        match (self, version) {
+            (Mode::Alphanumeric, 1..=9) => 9,
+            (Mode::Alphanumeric, 10..=26) => 11,
+            (Mode::Alphanumeric, _) => 13,
+            (Mode::Byte, 1..=9) => 8,
+            (Mode::Byte, _) => 16,
+        }
+    }
+
+    /// Bits of the encoded characters
+    fn data_bits(self, length: usize) -> usize {
+        match self {
+            Mode::Alphanumeric => length / 2 * 11 + length % 2 * 6,
+            Mode::Byte => length * 8,
+        }
+    }
+}
+
+const fn size_of_version(version: u8) -> u8 {
+    version * 4 + 17
+}
+
+/// Modules available for data and error correction
+const fn raw_data_modules(version: u8) -> usize {
+    let version = version as usize;
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        modules -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+/// Data codewords of a version and level
+fn data_codewords(version: u8, ecc: ErrorCorrection) -> usize {
+    let (level, version) = (ecc.index(), version as usize);
+    raw_data_modules(version as u8) / 8
+        - ECC_CODEWORDS_PER_BLOCK[level][version] as usize * ECC_BLOCKS[level][version] as usize
+}
+
+/// Centre coordinates of the alignment patterns in each direction
+fn alignment_positions(version: u8) -> impl Iterator<Item = u8> {
+    let count = if version == 1 { 0 } else { version / 7 + 2 };
+    let step = if count > 0 {
+        (version as u16 * 8 + count as u16 * 3 + 5) / (count as u16 * 4 - 4) * 2
+    } else {
+        0
+    } as u8;
+    let last = size_of_version(version) - 7;
+    (0..count).map(move |i| {
+        if i == 0 {
+            6
+        } else {
+            last - (count - 1 - i) * step
+        }
+    })
+}
+
+/// Is the module part of a function pattern, rather than data?
+fn is_function(version: u8, x: i32, y: i32) -> bool {
+    let size = size_of_version(version) as i32;
+    // Finder patterns, separators, format information and dark module
+    let (left, right, top, bottom) = (x < 9, x >= size - 8, y < 9, y >= size - 8);
+    if (top && (left || right)) || (left && bottom) {
+        return true;
+    }
+    // Timing patterns
+    if x == 6 || y == 6 {
+        return true;
+    }
+    // Version information
+    if version >= 7
+        && ((x < 6 && y >= size - 11 && y < size - 8) || (y < 6 && x >= size - 11 && x < size - 8))
+    {
+        return true;
+    }
+    alignment_positions(version).any(|cy| {
+        alignment_positions(version).any(|cx| {
+            is_alignment(version, cx as i32, cy as i32)
+                && (x - cx as i32).abs() <= 2
+                && (y - cy as i32).abs() <= 2
+        })
+    })
+}
+
+/// Is there an alignment pattern centred at `cx`, `cy`? Not on top of the
+/// finder patterns.
+fn is_alignment(version: u8, cx: i32, cy: i32) -> bool {
+    let last = size_of_version(version) as i32 - 7;
+    let corner = |c: i32| c == 6 || c == last;
+    !((cx == 6 && corner(cy)) || (cy == 6 && corner(cx)))
+}
+
+/// Data modules in placement order, upwards and downwards in columns of two
+/// modules from the right
+fn data_modules(version: u8) -> impl Iterator<Item = (i32, i32)> {
+    let size = size_of_version(version) as i32;
+    (0..(size - 1) / 2)
+        .flat_map(move |pair| {
+            // The column of the vertical timing pattern is skipped
+            let right = size - 1 - pair * 2;
+            let right = if right <= 6 { right - 1 } else { right };
+            let upward = (right + 1) & 2 == 0;
+            (0..size).flat_map(move |vertical| {
+                let y = if upward {
+                    size - 1 - vertical
+                } else {
+                    vertical
+                };
+                (0..2).map(move |column| (right - column, y))
+            })
+        })
+        .filter(move |&(x, y)| !is_function(version, x, y))
+}
+
+/// Format information, error correction level and mask with BCH code
+fn format_word(ecc: ErrorCorrection, mask: u8) -> u16 {
+    let data = ecc.format_bits() << 3 | mask as u16;
+    let mut remainder = data;
+    for _ in 0..10 {
+        remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+    }
+    (data << 10 | remainder) ^ 0x5412
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z = 0u8;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1d);
+        z ^= ((y >> i) & 1) * x;
+    }
+    z
+}
+
+/// Reed-Solomon generator polynomial, highest coefficient omitted
+fn reed_solomon_divisor(degree: usize) -> [u8; MAX_ECC_CODEWORDS] {
+    let mut divisor = [0u8; MAX_ECC_CODEWORDS];
+    divisor[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            divisor[j] = gf_multiply(divisor[j], root);
+            if j + 1 < degree {
+                divisor[j] ^= divisor[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    divisor
+}
+
+/// Reed-Solomon error correction codewords of a block
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8], remainder: &mut [u8]) {
+    for octet in remainder.iter_mut() {
+        *octet = 0;
+    }
+    for &octet in data {
+        let factor = octet ^ remainder[0];
+        remainder.copy_within(1.., 0);
+        let last = remainder.len() - 1;
+        remainder[last] = 0;
+        for (r, &d) in remainder.iter_mut().zip(divisor.iter()) {
+            *r ^= gf_multiply(d, factor);
+        }
+    }
+}
+
+/// Big endian bit writer
+struct BitWriter<'a> {
+    data: &'a mut [u8],
+    bits: usize,
+}
+
+impl<'a> BitWriter<'a> {
+    fn push(&mut self, value: u32, bits: usize) {
+        for i in (0..bits).rev() {
+            if (value >> i) & 1 != 0 {
+                self.data[self.bits / 8] |= 0x80 >> (self.bits % 8);
+            }
+            self.bits += 1;
+        }
+    }
+}
+
+/// Encoded QR code
+pub struct QrCode {
+    version: u8,
+    size: u8,
+    ecc: ErrorCorrection,
+    mask: u8,
+    modules: [u8; MODULE_OCTETS],
+}
+
+impl QrCode {
+    /// Encode text, using alphanumeric mode if possible
+    pub fn encode_text(text: &str, ecc: ErrorCorrection) -> Result<Self, Error> {
+        let mode = if text.bytes().all(|c| ALPHANUMERIC.contains(&c)) {
+            Mode::Alphanumeric
+        } else {
+            Mode::Byte
+        };
+        Self::encode(text.as_bytes(), mode, ecc)
+    }
+
+    /// Encode binary data in byte mode
+    pub fn encode_bytes(data: &[u8], ecc: ErrorCorrection) -> Result<Self, Error> {
+        Self::encode(data, Mode::Byte, ecc)
+    }
+
+    /// Encode data in the given mode
+    ///
+    /// Returns `FormatError` if the data contains characters not supported
+    /// by the mode and `CapacityError` if the data does not fit in the
+    /// largest version.
+    pub fn encode(data: &[u8], mode: Mode, ecc: ErrorCorrection) -> Result<Self, Error> {
+        if mode == Mode::Alphanumeric && !data.iter().all(|c| ALPHANUMERIC.contains(c)) {
+            return Err(Error::FormatError);
+        }
+        let version = (1..=MAX_VERSION)
+            .find(|&version| {
+                let count_bits = mode.count_bits(version);
+                data.len() < 1 << count_bits
+                    && 4 + count_bits + mode.data_bits(data.len())
+                        <= data_codewords(version, ecc) * 8
+            })
+            .ok_or(Error::CapacityError)?;
+
+        let mut codewords = [0u8; MAX_CODEWORDS];
+        let capacity = data_codewords(version, ecc);
+        let mut writer = BitWriter {
+            data: &mut codewords[..capacity],
+            bits: 0,
+        };
+        writer.push(mode.indicator(), 4);
+        writer.push(data.len() as u32, mode.count_bits(version));
+        match mode {
+            Mode::Alphanumeric => {
+                let value = |c: &u8| ALPHANUMERIC.iter().position(|a| a == c).unwrap_or(0) as u32;
+                for pair in data.chunks(2) {
+                    match pair {
+                        [a, b] => writer.push(value(a) * 45 + value(b), 11),
+                        [a] => writer.push(value(a), 6),
+                        _ => (),
+                    }
+                }
+            }
+            Mode::Byte => {
+                for &octet in data {
+                    writer.push(octet as u32, 8);
+                }
+            }
+        }
+        // Terminator and padding to a full octet
+        let terminator = core::cmp::min(4, capacity * 8 - writer.bits);
+        writer.push(0, terminator);
+        writer.push(0, (8 - writer.bits % 8) % 8);
+        let used = writer.bits / 8;
+        for (i, octet) in codewords[used..capacity].iter_mut().enumerate() {
+            *octet = if i % 2 == 0 { 0xec } else { 0x11 };
+        }
+
+        let mut code = Self {
+            version,
+            size: size_of_version(version),
+            ecc,
+            mask: 0,
+            modules: [0; MODULE_OCTETS],
+        };
+        code.add_error_correction(&mut codewords);
+        code.draw_function_patterns();
+        code.draw_codewords(&codewords);
+
+        // Use the mask with the lowest penalty
+        let mut best = (u32::MAX, 0);
+        for mask in 0..8 {
+            code.apply_mask(mask);
+            code.draw_format_bits(mask);
+            let penalty = code.penalty();
+            if penalty < best.0 {
+                best = (penalty, mask);
+            }
+            code.apply_mask(mask);
+        }
+        code.mask = best.1;
+        code.apply_mask(code.mask);
+        code.draw_format_bits(code.mask);
+        Ok(code)
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn error_correction(&self) -> ErrorCorrection {
+        self.ecc
+    }
+
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    /// Modules on each side, without quiet zone
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    /// Is the module dark? Modules outside the code are light.
+    pub fn module(&self, x: i32, y: i32) -> bool {
+        let size = self.size as i32;
+        if x < 0 || y < 0 || x >= size || y >= size {
+            return false;
+        }
+        let i = (y * size + x) as usize;
+        self.modules[i / 8] & (1 << (i % 8)) != 0
+    }
+
+    fn set_module(&mut self, x: i32, y: i32, dark: bool) {
+        let size = self.size as i32;
+        if x < 0 || y < 0 || x >= size || y >= size {
+            return;
+        }
+        let i = (y * size + x) as usize;
+        if dark {
+            self.modules[i / 8] |= 1 << (i % 8);
+        } else {
+            self.modules[i / 8] &= !(1 << (i % 8));
+        }
+    }
+
+    fn draw_function_patterns(&mut self) {
+        let size = self.size as i32;
+        for i in 0..size {
+            self.set_module(6, i, i % 2 == 0);
+            self.set_module(i, 6, i % 2 == 0);
+        }
+        for &(cx, cy) in &[(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4..=4i32 {
+                for dx in -4..=4i32 {
+                    let distance = core::cmp::max(dx.abs(), dy.abs());
+                    self.set_module(cx + dx, cy + dy, distance != 2 && distance != 4);
+                }
+            }
+        }
+        for cy in alignment_positions(self.version) {
+            for cx in alignment_positions(self.version) {
+                let (cx, cy) = (cx as i32, cy as i32);
+                if !is_alignment(self.version, cx, cy) {
+                    continue;
+                }
+                for dy in -2..=2i32 {
+                    for dx in -2..=2i32 {
+                        let distance = core::cmp::max(dx.abs(), dy.abs());
+                        self.set_module(cx + dx, cy + dy, distance != 1);
+                    }
+                }
+            }
+        }
+        if self.version >= 7 {
+            let version = self.version as u32;
+            let mut remainder = version;
+            for _ in 0..12 {
+                remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1f25);
+            }
+            let bits = version << 12 | remainder;
+            for i in 0..18 {
+                let dark = (bits >> i) & 1 != 0;
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                self.set_module(a, b, dark);
+                self.set_module(b, a, dark);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u8) {
+        let size = self.size as i32;
+        let bits = format_word(self.ecc, mask);
+        let bit = |i: i32| (bits >> i) & 1 != 0;
+        // Around the top left finder pattern
+        for i in 0..6 {
+            self.set_module(8, i, bit(i));
+        }
+        self.set_module(8, 7, bit(6));
+        self.set_module(8, 8, bit(7));
+        self.set_module(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_module(14 - i, 8, bit(i));
+        }
+        // Next to the other finder patterns
+        for i in 0..8 {
+            self.set_module(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_module(8, size - 15 + i, bit(i));
+        }
+        self.set_module(8, size - 8, true);
+    }
+
+    /// Append the error correction codewords of each block to the data
+    fn add_error_correction(&self, codewords: &mut [u8; MAX_CODEWORDS]) {
+        let layout = BlockLayout::new(self.version, self.ecc);
+        let divisor = reed_solomon_divisor(layout.ecc_length);
+        let mut remainder = [0u8; MAX_ECC_CODEWORDS];
+        for block in 0..layout.blocks {
+            let start = layout.data_start(block);
+            let end = start + layout.data_length(block);
+            let remainder = &mut remainder[..layout.ecc_length];
+            reed_solomon_remainder(&codewords[start..end], &divisor, remainder);
+            let ecc_start = layout.ecc_start(block);
+            codewords[ecc_start..ecc_start + layout.ecc_length].copy_from_slice(remainder);
+        }
+    }
+
+    /// Place the interleaved codewords in the zigzag pattern
+    fn draw_codewords(&mut self, codewords: &[u8; MAX_CODEWORDS]) {
+        let layout = BlockLayout::new(self.version, self.ecc);
+        let mut interleaved = layout.interleaved().map(|i| codewords[i]);
+        let mut octet = 0u8;
+        for (bit, (x, y)) in data_modules(self.version).enumerate() {
+            if bit % 8 == 0 {
+                // Remainder bits are light
+                octet = interleaved.next().unwrap_or(0);
+            }
+            self.set_module(x, y, octet & (0x80 >> (bit % 8)) != 0);
+        }
+    }
+
+    /// Invert the data modules selected by a mask pattern
+    fn apply_mask(&mut self, mask: u8) {
+        let size = self.size as i32;
+        for y in 0..size {
+            for x in 0..size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if invert && !is_function(self.version, x, y) {
+                    let dark = self.module(x, y);
+                    self.set_module(x, y, !dark);
+                }
+            }
+        }
+    }
+
+    /// Penalty of the module pattern, lower is easier to scan
+    fn penalty(&self) -> u32 {
+        let size = self.size as i32;
+        let mut penalty = 0;
+        // The module at position i along a row or column
+        let lines = |vertical: bool, line: i32| {
+            move |i: i32| {
+                if vertical {
+                    self.module(line, i)
+                } else {
+                    self.module(i, line)
+                }
+            }
+        };
+        for vertical in [false, true] {
+            for line in 0..size {
+                let module = lines(vertical, line);
+                // Runs of five or more modules of the same colour
+                let mut run = 0;
+                for i in 0..size {
+                    if i > 0 && module(i) == module(i - 1) {
+                        run += 1;
+                    } else {
+                        run = 1;
+                    }
+                    if run == 5 {
+                        penalty += 3;
+                    } else if run > 5 {
+                        penalty += 1;
+                    }
+                }
+                // Patterns similar to the finder pattern, the quiet zone is
+                // light
+                const FINDER: [bool; 11] = [
+                    true, false, true, true, true, false, true, false, false, false, false,
+                ];
+                for i in -4..size - 6 {
+                    let forward = (0..11).all(|j| module(i + j) == FINDER[j as usize]);
+                    let backward = (0..11).all(|j| module(i + j) == FINDER[10 - j as usize]);
+                    if forward || backward {
+                        penalty += 40;
+                    }
+                }
+            }
+        }
+        // Blocks of 2x2 modules of the same colour
+        let mut dark = 0;
+        for y in 0..size {
+            for x in 0..size {
+                let colour = self.module(x, y);
+                if colour {
+                    dark += 1;
+                }
+                if x + 1 < size
+                    && y + 1 < size
+                    && colour == self.module(x + 1, y)
+                    && colour == self.module(x, y + 1)
+                    && colour == self.module(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+        // Balance of dark and light modules, in steps of 5 % from 50 %
+        let total = size * size;
+        let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+        penalty + k as u32 * 10
+    }
+}
+
+/// Division of the codewords into error correction blocks
+///
+/// The codewords are stored with the data codewords of all blocks first,
+/// followed by the error correction codewords of all blocks.
+struct BlockLayout {
+    blocks: usize,
+    short_blocks: usize,
+    // data codewords of a short block, long blocks have one more
+    short_data: usize,
+    ecc_length: usize,
+    data_length: usize,
+}
+
+impl BlockLayout {
+    fn new(version: u8, ecc: ErrorCorrection) -> Self {
+        let (level, index) = (ecc.index(), version as usize);
+        let blocks = ECC_BLOCKS[level][index] as usize;
+        let ecc_length = ECC_CODEWORDS_PER_BLOCK[level][index] as usize;
+        let raw = raw_data_modules(version) / 8;
+        Self {
+            blocks,
+            short_blocks: blocks - raw % blocks,
+            short_data: raw / blocks - ecc_length,
+            ecc_length,
+            data_length: data_codewords(version, ecc),
+        }
+    }
+
+    fn data_length(&self, block: usize) -> usize {
+        self.short_data + (block >= self.short_blocks) as usize
+    }
+
+    fn data_start(&self, block: usize) -> usize {
+        block * self.short_data + block.saturating_sub(self.short_blocks)
+    }
+
+    fn ecc_start(&self, block: usize) -> usize {
+        self.data_length + block * self.ecc_length
+    }
+
+    /// Indices of the codewords in transmission order, the codewords of the
+    /// blocks interleaved
+    fn interleaved(&self) -> impl Iterator<Item = usize> + '_ {
+        let length = self.short_data + 1 + self.ecc_length;
+        (0..length).flat_map(move |i| {
+            (0..self.blocks).filter_map(move |block| {
+                if i < self.data_length(block) {
+                    Some(self.data_start(block) + i)
+                } else if i > self.short_data {
+                    Some(self.ecc_start(block) + i - self.short_data - 1)
+                } else {
+                    None
+                }
+            })
+        })
+    }
+}
+
+/// Drawing of a QR code with quiet zone
+#[cfg(feature = "graphics")]
+pub struct QrView<'a> {
+    code: &'a QrCode,
+    top_left: Point,
+    scale: u32,
+    dark: Palette8,
+    light: Palette8,
+}
+
+#[cfg(feature = "graphics")]
+impl<'a> QrView<'a> {
+    /// Code with its top left corner, including the quiet zone, at
+    /// `top_left`, each module `scale` by `scale` pixels
+    pub fn new(code: &'a QrCode, top_left: Point, scale: u32) -> Self {
+        Self {
+            code,
+            top_left,
+            scale: core::cmp::max(scale, 1),
+            dark: Palette8::Black,
+            light: Palette8::White,
+        }
+    }
+
+    /// Code at the largest scale fitting the area, centred in the area
+    pub fn fit(code: &'a QrCode, area: Rectangle) -> Self {
+        let modules = code.size as u32 + 2 * QUIET_ZONE;
+        let scale = core::cmp::min(area.size.width, area.size.height) / modules;
+        let mut view = Self::new(code, Point::zero(), scale);
+        let size = view.size();
+        view.top_left = area.top_left
+            + Point::new(
+                (area.size.width as i32 - size.width as i32) / 2,
+                (area.size.height as i32 - size.height as i32) / 2,
+            );
+        view
+    }
+
+    /// Colours of dark and light modules, a code is easiest to scan dark on
+    /// light
+    pub fn with_colours(self, dark: Palette8, light: Palette8) -> Self {
+        Self {
+            dark,
+            light,
+            ..self
+        }
+    }
+
+    /// Size including the quiet zone
+    pub fn size(&self) -> Size {
+        let side = (self.code.size as u32 + 2 * QUIET_ZONE) * self.scale;
+        Size::new(side, side)
+    }
+
+    pub fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(self.top_left, self.size())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<'a> Drawable for QrView<'a> {
+    type Color = Palette8;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let area = self.bounding_box();
+        let (scale, quiet_zone) = (self.scale as i32, QUIET_ZONE as i32);
+        let colours = area.points().map(|point| {
+            let offset = point - self.top_left;
+            let (x, y) = (offset.x / scale - quiet_zone, offset.y / scale - quiet_zone);
+            if self.code.module(x, y) {
+                self.dark
+            } else {
+                self.light
+            }
+        });
+        target.fill_contiguous(&area, colours)
+    }
+}
@@ -0,0 +1,172 @@
+//! # Screenshots of the frame buffer
+//!
+//! The frame buffer is run length encoded as a [`RleImage`](super::RleImage)
+//! in display coordinates and logged in chunks with `defmt`. The
+//! `screenshot` example finds the chunks in the captured log and writes a
+//! PNG image.
+//!
+//! ```text
+//! screenshot begin
+//! screenshot 0 [80, 56, 176, 0, 176, 0, ...]
+//! screenshot 1 [...]
+//! screenshot end 2 130
+//! ```
+//!
+//! Each chunk has a sequence number, the end line holds the number of chunks
+//! and octets, so lost log lines are detected.
+
+use crate::lpm013m126a::{image, FrameBuffer, Panel};
+
+/// Octets per logged chunk
+pub const CHUNK_OCTETS: usize = 64;
+
+/// Screenshot encoded a few lines at a time
+///
+/// The frame buffer is only needed while encoding lines, so it can be drawn
+/// and sent in between. The screenshot may then show parts of two frames.
+pub struct Capture {
+    encoder: image::Encoder,
+    chunk: [u8; CHUNK_OCTETS],
+    length: usize,
+    // next line to encode
+    line: u16,
+    sequence: u16,
+    octets: u32,
+    complete: bool,
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Self {
+            encoder: image::Encoder::new(),
+            chunk: [0u8; CHUNK_OCTETS],
+            length: 0,
+            line: 0,
+            sequence: 0,
+            octets: 0,
+            complete: false,
+        }
+    }
+
+    /// All lines are encoded and passed on
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Number of chunks passed on
+    pub fn chunks(&self) -> u16 {
+        self.sequence
+    }
+
+    /// Number of octets passed on
+    pub fn octets(&self) -> u32 {
+        self.octets
+    }
+
+    /// Encode at most `lines` lines, passing the sequence number and chunks
+    /// of at most `CHUNK_OCTETS` to `write`
+    ///
+    /// Returns `true` once the last chunk is passed on.
+    pub fn encode_lines<P, S, F>(
+        &mut self,
+        frame_buffer: &FrameBuffer<P, S>,
+        lines: u16,
+        mut write: F,
+    ) -> bool
+    where
+        P: Panel,
+        S: AsRef<[u8]> + AsMut<[u8]>,
+        F: FnMut(u16, &[u8]),
+    {
+        if self.complete {
+            return true;
+        }
+        let Self {
+            encoder,
+            chunk,
+            length,
+            line,
+            sequence,
+            octets,
+            complete,
+        } = self;
+        let mut flush = |chunk: &[u8]| {
+            write(*sequence, chunk);
+            *sequence = sequence.wrapping_add(1);
+            *octets += chunk.len() as u32;
+        };
+        let mut push = |data: &[u8]| {
+            for &octet in data {
+                if *length == CHUNK_OCTETS {
+                    flush(&chunk[..]);
+                    *length = 0;
+                }
+                chunk[*length] = octet;
+                *length += 1;
+            }
+        };
+        if *line == 0 {
+            push(&image::header(P::WIDTH, P::HEIGHT));
+        }
+        let end = line.saturating_add(lines.max(1)).min(P::HEIGHT);
+        for y in *line..end {
+            for x in 0..P::WIDTH {
+                encoder.push(frame_buffer.read_pixel(x, y), &mut push);
+            }
+        }
+        *line = end;
+        if end == P::HEIGHT {
+            encoder.finish(&mut push);
+            if *length > 0 {
+                flush(&chunk[..*length]);
+            }
+            *complete = true;
+        }
+        *complete
+    }
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Encode the frame buffer, passing chunks of at most `CHUNK_OCTETS` to
+/// `write`
+pub fn capture<P, S, F>(frame_buffer: &FrameBuffer<P, S>, mut write: F)
+where
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+    F: FnMut(&[u8]),
+{
+    Capture::new().encode_lines(frame_buffer, P::HEIGHT, |_, chunk| write(chunk));
+}
+
+/// Log at most `lines` lines of a screenshot of the frame buffer
+///
+/// Returns `true` once the screenshot is logged completely.
+#[cfg(feature = "hardware")]
+pub fn log<P, S>(capture: &mut Capture, frame_buffer: &FrameBuffer<P, S>, lines: u16) -> bool
+where
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+{
+    if capture.is_complete() {
+        return true;
+    }
+    if capture.line == 0 {
+        defmt::info!("screenshot begin");
+    }
+    let complete = capture.encode_lines(frame_buffer, lines, |sequence, chunk| {
+        defmt::info!("screenshot {=u16} {=[u8]}", sequence, chunk);
+    });
+    if complete {
+        defmt::info!(
+            "screenshot end {=u16} {=u32}",
+            capture.chunks(),
+            capture.octets()
+        );
+    }
+    complete
+}
@@ -0,0 +1,139 @@
+//! # Host rendering of the frame buffer
+//!
+//! Renders a [`FrameBuffer`] the way the panel shows it, so screens can be
+//! designed and compared against golden images without flashing a board.
+//!
+//! Images are written as binary PPM, or PNG with the `simulator` feature.
+//! With the `graphics` feature the frame buffer can also be drawn to any
+//! `Rgb888` draw target, such as a `SimulatorDisplay` from
+//! `embedded-graphics-simulator`.
+
+use crate::lpm013m126a::{FrameBuffer, Palette8, Panel};
+use std::io::{self, BufRead, Write};
+
+#[cfg(feature = "graphics")]
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::Rgb888,
+    primitives::Rectangle,
+};
+
+fn rgb(colour: Palette8) -> [u8; 3] {
+    let (r, g, b) = colour.rgb();
+    [r as u8 * 0xff, g as u8 * 0xff, b as u8 * 0xff]
+}
+
+/// The pixels of the frame buffer in display coordinates as RGB triplets
+pub fn rgb888<P, S>(frame_buffer: &FrameBuffer<P, S>) -> Vec<u8>
+where
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+{
+    let mut data = Vec::with_capacity(P::WIDTH as usize * P::HEIGHT as usize * 3);
+    for y in 0..P::HEIGHT {
+        for x in 0..P::WIDTH {
+            data.extend_from_slice(&rgb(frame_buffer.read_pixel(x, y)));
+        }
+    }
+    data
+}
+
+/// Write the frame buffer as a binary PPM image
+pub fn write_ppm<P, S, W>(frame_buffer: &FrameBuffer<P, S>, mut writer: W) -> io::Result<()>
+where
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+    W: Write,
+{
+    write!(writer, "P6\n{} {}\n255\n", P::WIDTH, P::HEIGHT)?;
+    writer.write_all(&rgb888(frame_buffer))?;
+    writer.flush()
+}
+
+/// Read a binary PPM image, returns the width, height and RGB triplets
+pub fn read_ppm<R: BufRead>(mut reader: R) -> io::Result<(u32, u32, Vec<u8>)> {
+    let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut fields = Vec::new();
+    let mut line = String::new();
+    while fields.len() < 4 {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid("Truncated PPM header"));
+        }
+        let content = line.split('#').next().unwrap_or("");
+        fields.extend(content.split_whitespace().map(String::from));
+    }
+    if fields[0] != "P6" || fields[3] != "255" {
+        return Err(invalid("Unsupported PPM format"));
+    }
+    let width: u32 = fields[1]
+        .parse()
+        .map_err(|_| invalid("Invalid PPM width"))?;
+    let height: u32 = fields[2]
+        .parse()
+        .map_err(|_| invalid("Invalid PPM height"))?;
+    let mut data = vec![0u8; width as usize * height as usize * 3];
+    reader.read_exact(&mut data)?;
+    Ok((width, height, data))
+}
+
+/// Count the pixels differing from a golden PPM image
+///
+/// An image of a different size differs in every pixel.
+pub fn compare_ppm<P, S, R>(frame_buffer: &FrameBuffer<P, S>, golden: R) -> io::Result<usize>
+where
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+    R: BufRead,
+{
+    let pixels = P::WIDTH as usize * P::HEIGHT as usize;
+    let (width, height, data) = read_ppm(golden)?;
+    if width != P::WIDTH as u32 || height != P::HEIGHT as u32 {
+        return Ok(pixels);
+    }
+    let rendered = rgb888(frame_buffer);
+    Ok(rendered
+        .chunks(3)
+        .zip(data.chunks(3))
+        .filter(|(a, b)| a != b)
+        .count())
+}
+
+/// Write the frame buffer as a PNG image
+#[cfg(feature = "png")]
+pub fn write_png<P, S, W>(
+    frame_buffer: &FrameBuffer<P, S>,
+    writer: W,
+) -> Result<(), png::EncodingError>
+where
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+    W: Write,
+{
+    let mut encoder = png::Encoder::new(writer, P::WIDTH as u32, P::HEIGHT as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&rgb888(frame_buffer))
+}
+
+/// Draw the frame buffer, in display coordinates, at `top_left` of an RGB
+/// draw target
+#[cfg(feature = "graphics")]
+pub fn draw_to<P, S, D>(
+    frame_buffer: &FrameBuffer<P, S>,
+    target: &mut D,
+    top_left: Point,
+) -> Result<(), D::Error>
+where
+    P: Panel,
+    S: AsRef<[u8]> + AsMut<[u8]>,
+    D: DrawTarget<Color = Rgb888>,
+{
+    let area = Rectangle::new(top_left, Size::new(P::WIDTH as u32, P::HEIGHT as u32));
+    let pixels = (0..P::HEIGHT)
+        .flat_map(|y| (0..P::WIDTH).map(move |x| (x, y)))
+        .map(|(x, y)| Rgb888::from(frame_buffer.read_pixel(x, y)));
+    target.fill_contiguous(&area, pixels)
+}
@@ -0,0 +1,454 @@
+//! # Sprites and tile maps
+//!
+//! A [`Scene`] is a background tile map with sprites on top of it, rendered
+//! one line at a time. Tiles and sprite frames are taken from a
+//! [`TileSheet`], an image of 4-bit `Palette8` indices in the same layout as
+//! `ImageRaw<Palette8>`, two pixels per octet with the left pixel in the most
+//! significant nibble.
+//!
+//! Sprites have a colour key which is not drawn, can be flipped and are drawn
+//! in order of their z value, higher on top. Changing a tile or a sprite
+//! marks the lines it covers, before and after the change, as dirty.
+//!
+//! A scene covers the `DISPLAY_WIDTH` by `DISPLAY_HEIGHT` pixels of the
+//! LPM013M126A. With the `hardware` feature the scene is a [`LineRenderer`]
+//! for a [`LineDisplay`](super::LineDisplay) of that panel, which only
+//! renders and sends the dirty lines. Moving a few small sprites then sends a
+//! few dozen lines per frame instead of the full display. With the `graphics`
+//! feature the dirty lines can also be drawn to any draw target, such as a
+//! `FrameBuffer`.
+
+use crate::{
+    lpm013m126a::{Palette8, DISPLAY_HEIGHT, DISPLAY_WIDTH},
+    Error,
+};
+
+#[cfg(feature = "hardware")]
+use crate::lpm013m126a::{panel, Line, LineRenderer, Panel};
+#[cfg(feature = "graphics")]
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    primitives::Rectangle,
+};
+
+const DIRTY_WORDS: usize = (DISPLAY_HEIGHT as usize + 31) / 32;
+
+/// Image divided into tiles of equal size
+#[derive(Clone, Copy)]
+pub struct TileSheet<'a> {
+    data: &'a [u8],
+    row_octets: usize,
+    columns: u16,
+    tiles: u16,
+    tile_width: u16,
+    tile_height: u16,
+}
+
+impl<'a> TileSheet<'a> {
+    /// Sheet of 4-bit pixels, `width` pixels wide
+    ///
+    /// Tiles are numbered left to right, top to bottom. The width must be a
+    /// non-zero multiple of the tile width and the data hold whole rows of
+    /// tiles.
+    pub fn new(
+        data: &'a [u8],
+        width: u16,
+        tile_width: u16,
+        tile_height: u16,
+    ) -> Result<Self, Error> {
+        if tile_width == 0 || tile_height == 0 || width < tile_width || width % tile_width != 0 {
+            return Err(Error::FormatError);
+        }
+        let row_octets = (width as usize + 1) / 2;
+        let tile_row_octets = row_octets * tile_height as usize;
+        if data.is_empty() || data.len() % tile_row_octets != 0 {
+            return Err(Error::BufferSizeError);
+        }
+        let columns = width / tile_width;
+        let tiles = (data.len() / tile_row_octets) * columns as usize;
+        Ok(Self {
+            data,
+            row_octets,
+            columns,
+            tiles: core::cmp::min(tiles, u16::MAX as usize) as u16,
+            tile_width,
+            tile_height,
+        })
+    }
+
+    /// Number of tiles
+    pub fn len(&self) -> u16 {
+        self.tiles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles == 0
+    }
+
+    pub fn tile_width(&self) -> u16 {
+        self.tile_width
+    }
+
+    pub fn tile_height(&self) -> u16 {
+        self.tile_height
+    }
+
+    /// Colour of a pixel within a tile, tiles past the end use the last tile
+    pub fn pixel(&self, tile: u16, x: u16, y: u16) -> Palette8 {
+        let tile = core::cmp::min(tile, self.tiles - 1);
+        let px = (tile % self.columns) as usize * self.tile_width as usize + x as usize;
+        let py = (tile / self.columns) as usize * self.tile_height as usize + y as usize;
+        let octet = self.data[py * self.row_octets + px / 2];
+        let index = if px & 1 == 0 { octet >> 4 } else { octet };
+        Palette8::from(index & 0x7)
+    }
+}
+
+/// A tile of a tile sheet drawn on top of the background
+#[derive(Clone, Copy)]
+pub struct Sprite<'a> {
+    sheet: TileSheet<'a>,
+    key: Option<Palette8>,
+    /// Left edge in display coordinates
+    pub x: i16,
+    /// Top edge in display coordinates
+    pub y: i16,
+    /// Tile of the sheet
+    pub tile: u16,
+    /// Sprites with higher z are drawn on top
+    pub z: i8,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    pub visible: bool,
+}
+
+impl<'a> Sprite<'a> {
+    /// Visible sprite at the origin, without transparent colour
+    pub fn new(sheet: TileSheet<'a>, tile: u16) -> Self {
+        Self {
+            sheet,
+            key: None,
+            x: 0,
+            y: 0,
+            tile,
+            z: 0,
+            flip_horizontal: false,
+            flip_vertical: false,
+            visible: true,
+        }
+    }
+
+    /// Pixels of `colour` are transparent
+    pub fn with_key(mut self, colour: Palette8) -> Self {
+        self.key = Some(colour);
+        self
+    }
+
+    pub fn with_position(mut self, x: i16, y: i16) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    pub fn with_z(mut self, z: i8) -> Self {
+        self.z = z;
+        self
+    }
+
+    pub fn width(&self) -> u16 {
+        self.sheet.tile_width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.sheet.tile_height
+    }
+
+    /// Display lines covered by the sprite, first and last
+    fn lines(&self) -> Option<(u16, u16)> {
+        let top = self.y as i32;
+        let bottom = top + self.height() as i32 - 1;
+        if !self.visible || bottom < 0 || top >= DISPLAY_HEIGHT as i32 {
+            return None;
+        }
+        Some((
+            core::cmp::max(top, 0) as u16,
+            core::cmp::min(bottom, DISPLAY_HEIGHT as i32 - 1) as u16,
+        ))
+    }
+
+    /// Draw the sprite pixels of display line `line`
+    fn render_line<F>(&self, line: u16, width: u16, put: &mut F)
+    where
+        F: FnMut(u16, Palette8),
+    {
+        let row = line as i32 - self.y as i32;
+        if !self.visible || row < 0 || row >= self.height() as i32 {
+            return;
+        }
+        let row = if self.flip_vertical {
+            self.height() - 1 - row as u16
+        } else {
+            row as u16
+        };
+        let first = core::cmp::max(-(self.x as i32), 0) as u16;
+        let last = core::cmp::min(self.width() as i32, width as i32 - self.x as i32);
+        for column in first..core::cmp::max(last, 0) as u16 {
+            let source = if self.flip_horizontal {
+                self.width() - 1 - column
+            } else {
+                column
+            };
+            let colour = self.sheet.pixel(self.tile, source, row);
+            if Some(colour) != self.key {
+                put((self.x as i32 + column as i32) as u16, colour);
+            }
+        }
+    }
+}
+
+/// Identifier of a sprite in a scene
+#[derive(Clone, Copy, PartialEq)]
+pub struct SpriteId(usize);
+
+/// Background tile map with up to `SPRITES` sprites
+pub struct Scene<'a, const COLUMNS: usize, const ROWS: usize, const SPRITES: usize> {
+    sheet: TileSheet<'a>,
+    tiles: [[u8; COLUMNS]; ROWS],
+    background: Palette8,
+    sprites: [Option<Sprite<'a>>; SPRITES],
+    // sprite indices, lowest z first
+    order: [usize; SPRITES],
+    count: usize,
+    dirty: [u32; DIRTY_WORDS],
+}
+
+impl<'a, const COLUMNS: usize, const ROWS: usize, const SPRITES: usize>
+    Scene<'a, COLUMNS, ROWS, SPRITES>
+{
+    /// Scene where every tile is tile 0 of `sheet`, all lines dirty
+    ///
+    /// Pixels outside the tile map are `background`.
+    pub fn new(sheet: TileSheet<'a>, background: Palette8) -> Self {
+        let mut scene = Self {
+            sheet,
+            tiles: [[0; COLUMNS]; ROWS],
+            background,
+            sprites: [None; SPRITES],
+            order: [0; SPRITES],
+            count: 0,
+            dirty: [0; DIRTY_WORDS],
+        };
+        scene.mark_all_dirty();
+        scene
+    }
+
+    pub fn tile(&self, column: usize, row: usize) -> Option<u8> {
+        self.tiles.get(row)?.get(column).copied()
+    }
+
+    pub fn set_tile(&mut self, column: usize, row: usize, tile: u8) -> Result<(), Error> {
+        let t = self
+            .tiles
+            .get_mut(row)
+            .and_then(|r| r.get_mut(column))
+            .ok_or(Error::OutOfBoundsError)?;
+        if *t != tile {
+            *t = tile;
+            let top = row * self.sheet.tile_height as usize;
+            let bottom = top + self.sheet.tile_height as usize - 1;
+            self.mark_dirty(top, bottom);
+        }
+        Ok(())
+    }
+
+    /// Set every tile of the map
+    pub fn fill_tiles(&mut self, tile: u8) {
+        self.tiles = [[tile; COLUMNS]; ROWS];
+        self.mark_all_dirty();
+    }
+
+    /// Add a sprite, fails when all `SPRITES` slots are used
+    pub fn add_sprite(&mut self, sprite: Sprite<'a>) -> Result<SpriteId, Error> {
+        let index = self
+            .sprites
+            .iter()
+            .position(Option::is_none)
+            .ok_or(Error::CapacityError)?;
+        self.sprites[index] = Some(sprite);
+        self.mark_sprite(&sprite);
+        self.sort();
+        Ok(SpriteId(index))
+    }
+
+    pub fn remove_sprite(&mut self, id: SpriteId) -> Option<Sprite<'a>> {
+        let sprite = self.sprites.get_mut(id.0)?.take()?;
+        self.mark_sprite(&sprite);
+        self.sort();
+        Some(sprite)
+    }
+
+    pub fn sprite(&self, id: SpriteId) -> Option<&Sprite<'a>> {
+        self.sprites.get(id.0)?.as_ref()
+    }
+
+    /// Change a sprite, the lines it covered and covers are marked dirty
+    pub fn update_sprite<F>(&mut self, id: SpriteId, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Sprite<'a>),
+    {
+        let sprite = self
+            .sprites
+            .get_mut(id.0)
+            .and_then(Option::as_mut)
+            .ok_or(Error::OutOfBoundsError)?;
+        let before = *sprite;
+        f(sprite);
+        let after = *sprite;
+        if before.lines() != after.lines()
+            || before.x != after.x
+            || before.tile != after.tile
+            || before.flip_horizontal != after.flip_horizontal
+            || before.flip_vertical != after.flip_vertical
+            || before.z != after.z
+        {
+            self.mark_sprite(&before);
+            self.mark_sprite(&after);
+        }
+        if before.z != after.z {
+            self.sort();
+        }
+        Ok(())
+    }
+
+    /// Move a sprite to a new position
+    pub fn move_sprite(&mut self, id: SpriteId, x: i16, y: i16) -> Result<(), Error> {
+        self.update_sprite(id, |sprite| {
+            sprite.x = x;
+            sprite.y = y;
+        })
+    }
+
+    /// Does the line need to be redrawn?
+    pub fn is_line_dirty(&self, line: u16) -> bool {
+        match self.dirty.get(line as usize / 32) {
+            Some(word) => word & (1 << (line % 32)) != 0,
+            None => true,
+        }
+    }
+
+    /// Does any line need to be redrawn?
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.iter().any(|&word| word != 0)
+    }
+
+    pub fn mark_all_dirty(&mut self) {
+        self.mark_dirty(0, DISPLAY_HEIGHT as usize - 1);
+    }
+
+    fn mark_dirty(&mut self, first: usize, last: usize) {
+        let last = core::cmp::min(last, DISPLAY_HEIGHT as usize - 1);
+        for line in first..=last {
+            self.dirty[line / 32] |= 1 << (line % 32);
+        }
+    }
+
+    fn clear_dirty(&mut self, line: u16) {
+        if let Some(word) = self.dirty.get_mut(line as usize / 32) {
+            *word &= !(1 << (line % 32));
+        }
+    }
+
+    fn mark_sprite(&mut self, sprite: &Sprite<'a>) {
+        if let Some((first, last)) = sprite.lines() {
+            self.mark_dirty(first as usize, last as usize);
+        }
+    }
+
+    /// Order the sprites by z, keeping the order of sprites with equal z
+    fn sort(&mut self) {
+        self.count = 0;
+        for (index, sprite) in self.sprites.iter().enumerate() {
+            if let Some(sprite) = sprite {
+                let mut i = self.count;
+                while i > 0 && self.z(self.order[i - 1]) > sprite.z {
+                    self.order[i] = self.order[i - 1];
+                    i -= 1;
+                }
+                self.order[i] = index;
+                self.count += 1;
+            }
+        }
+    }
+
+    fn z(&self, index: usize) -> i8 {
+        self.sprites[index].map_or(0, |sprite| sprite.z)
+    }
+
+    /// Render the pixels of `line` from the left edge to `width`
+    ///
+    /// Pixels covered by sprites are produced more than once, the last
+    /// colour of a pixel is the one shown.
+    pub fn render_pixels<F>(&self, line: u16, width: u16, mut put: F)
+    where
+        F: FnMut(u16, Palette8),
+    {
+        let tile_width = self.sheet.tile_width;
+        let tile_height = self.sheet.tile_height;
+        let row = self.tiles.get((line / tile_height) as usize);
+        for x in 0..width {
+            let tile = row.and_then(|r| r.get((x / tile_width) as usize));
+            let colour = match tile {
+                Some(&tile) => self
+                    .sheet
+                    .pixel(tile as u16, x % tile_width, line % tile_height),
+                None => self.background,
+            };
+            put(x, colour);
+        }
+        for &index in self.order[..self.count].iter() {
+            if let Some(sprite) = &self.sprites[index] {
+                sprite.render_line(line, width, &mut put);
+            }
+        }
+    }
+
+    /// Draw the dirty lines to a target and mark them clean
+    #[cfg(feature = "graphics")]
+    pub fn draw<D>(&mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        let mut pixels = [self.background; DISPLAY_WIDTH as usize];
+        for line in 0..u16::from(DISPLAY_HEIGHT) {
+            if !self.is_line_dirty(line) {
+                continue;
+            }
+            let width = u16::from(DISPLAY_WIDTH);
+            self.render_pixels(line, width, |x, colour| pixels[x as usize] = colour);
+            let area = Rectangle::new(
+                Point::new(0, line as i32),
+                Size::new(DISPLAY_WIDTH as u32, 1),
+            );
+            target.fill_contiguous(&area, pixels.iter().copied())?;
+            self.clear_dirty(line);
+        }
+        Ok(())
+    }
+}
+
+// The dirty lines are sized for the LPM013M126A
+#[cfg(feature = "hardware")]
+impl<'a, const COLUMNS: usize, const ROWS: usize, const SPRITES: usize>
+    LineRenderer<panel::Lpm013m126a> for Scene<'a, COLUMNS, ROWS, SPRITES>
+{
+    fn render_line(&mut self, line: u16, pixels: &mut Line<panel::Lpm013m126a>) {
+        let width = panel::Lpm013m126a::WIDTH;
+        self.render_pixels(line, width, |x, colour| pixels.set_pixel(x, colour));
+        self.clear_dirty(line);
+    }
+
+    fn is_line_changed(&self, line: u16) -> bool {
+        self.is_line_dirty(line)
+    }
+}
@@ -0,0 +1,169 @@
+//! # Frame timing statistics
+//!
+//! The display drivers record when frames start and end, the number of lines
+//! sent and how many `update_display()` requests were coalesced into a frame
+//! already pending. Timestamps are read from a clock set with `set_clock()`,
+//! such as the DWT cycle counter, without a clock only counts are recorded.
+//!
+//! ```ignore
+//! jdi.set_clock(DWT::cycle_count, 64_000_000);
+//! defmt::info!("{}", jdi.stats());
+//! ```
+
+/// Timing of the frames sent by a display driver
+#[derive(Clone, Copy, Default)]
+pub struct FrameStats {
+    clock: Option<fn() -> u32>,
+    ticks_per_second: u32,
+    frames: u32,
+    requests: u32,
+    coalesced: u32,
+    lines: u32,
+    frame_lines: u32,
+    start: u32,
+    frame_ticks: u32,
+    min_frame_ticks: u32,
+    max_frame_ticks: u32,
+    interval_ticks: u32,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clock returning a free running tick count, wrapping is handled
+    pub fn set_clock(&mut self, clock: fn() -> u32, ticks_per_second: u32) {
+        self.clock = Some(clock);
+        self.ticks_per_second = ticks_per_second;
+    }
+
+    /// Clear the statistics, the clock is kept
+    pub fn reset(&mut self) {
+        *self = Self {
+            clock: self.clock,
+            ticks_per_second: self.ticks_per_second,
+            ..Self::default()
+        };
+    }
+
+    fn now(&self) -> u32 {
+        self.clock.map_or(0, |clock| clock())
+    }
+
+    pub(crate) fn request(&mut self, coalesced: bool) {
+        self.requests = self.requests.wrapping_add(1);
+        if coalesced {
+            self.coalesced = self.coalesced.wrapping_add(1);
+        }
+    }
+
+    pub(crate) fn frame_start(&mut self) {
+        let now = self.now();
+        if self.frames > 0 {
+            self.interval_ticks = now.wrapping_sub(self.start);
+        }
+        self.start = now;
+        self.lines = 0;
+    }
+
+    pub(crate) fn line_sent(&mut self) {
+        self.lines += 1;
+    }
+
+    pub(crate) fn frame_end(&mut self) {
+        let ticks = self.now().wrapping_sub(self.start);
+        if self.frames == 0 || ticks < self.min_frame_ticks {
+            self.min_frame_ticks = ticks;
+        }
+        if ticks > self.max_frame_ticks {
+            self.max_frame_ticks = ticks;
+        }
+        self.frame_ticks = ticks;
+        self.frame_lines = self.lines;
+        self.frames = self.frames.wrapping_add(1);
+    }
+
+    /// Number of frames sent
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Number of `update_display()` requests
+    pub fn requests(&self) -> u32 {
+        self.requests
+    }
+
+    /// Requests made while an update was already pending, these do not
+    /// result in a frame of their own
+    pub fn coalesced(&self) -> u32 {
+        self.coalesced
+    }
+
+    /// Lines sent in the last frame
+    pub fn lines_per_frame(&self) -> u32 {
+        self.frame_lines
+    }
+
+    /// Duration of the last frame in microseconds
+    pub fn frame_time_us(&self) -> u32 {
+        self.to_us(self.frame_ticks)
+    }
+
+    /// Shortest frame in microseconds
+    pub fn min_frame_time_us(&self) -> u32 {
+        self.to_us(self.min_frame_ticks)
+    }
+
+    /// Longest frame in microseconds
+    pub fn max_frame_time_us(&self) -> u32 {
+        self.to_us(self.max_frame_ticks)
+    }
+
+    /// Time between the starts of the last two frames in microseconds
+    pub fn interval_us(&self) -> u32 {
+        self.to_us(self.interval_ticks)
+    }
+
+    /// Frames per second at the current update rate
+    pub fn fps(&self) -> u32 {
+        self.per_second(self.interval_ticks)
+    }
+
+    /// Frames per second if frames were sent back to back
+    pub fn max_fps(&self) -> u32 {
+        self.per_second(self.frame_ticks)
+    }
+
+    fn to_us(&self, ticks: u32) -> u32 {
+        if self.ticks_per_second == 0 {
+            return 0;
+        }
+        (ticks as u64 * 1_000_000 / self.ticks_per_second as u64) as u32
+    }
+
+    fn per_second(&self, ticks: u32) -> u32 {
+        if ticks == 0 {
+            return 0;
+        }
+        self.ticks_per_second / ticks
+    }
+}
+
+impl defmt::Format for FrameStats {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "{=u32} frames, {=u32} lines, {=u32} us ({=u32}..{=u32}), {=u32} fps of {=u32}, {=u32} requests, {=u32} coalesced",
+            self.frames,
+            self.frame_lines,
+            self.frame_time_us(),
+            self.min_frame_time_us(),
+            self.max_frame_time_us(),
+            self.fps(),
+            self.max_fps(),
+            self.requests,
+            self.coalesced
+        )
+    }
+}
@@ -0,0 +1,234 @@
+//! # Text layout on the display
+//!
+//! [`TextBox`] draws text inside a rectangle using the ProFont monospace
+//! fonts. Text is wrapped at word boundaries, words wider than the rectangle
+//! are broken, and explicit line breaks are kept. Each line is aligned to the
+//! left, centre or right of the rectangle.
+//!
+//! Text not fitting the rectangle is not drawn. With ellipsis the last
+//! visible line then ends in `...`. The text not drawn is returned from
+//! `draw()`, so long texts can be split over several boxes or pages.
+
+use crate::lpm013m126a::Palette8;
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    mono_font::{MonoFont, MonoTextStyle},
+    primitives::Rectangle,
+    text::{Baseline, Text},
+    Drawable,
+};
+
+/// Font sizes available for text
+#[derive(Clone, Copy, PartialEq)]
+pub enum Font {
+    /// ProFont 7 point, 5x9 pixels per character
+    Small,
+    /// ProFont 10 point, 7x13 pixels per character
+    Medium,
+    /// ProFont 14 point, 10x18 pixels per character
+    Large,
+    /// ProFont 24 point, 16x32 pixels per character
+    Huge,
+}
+
+impl Font {
+    pub fn mono_font(self) -> &'static MonoFont<'static> {
+        match self {
+            Font::Small => &profont::PROFONT_7_POINT,
+            Font::Medium => &profont::PROFONT_10_POINT,
+            Font::Large => &profont::PROFONT_14_POINT,
+            Font::Huge => &profont::PROFONT_24_POINT,
+        }
+    }
+
+    /// Horizontal distance between the start of two characters
+    pub fn character_width(self) -> u32 {
+        let font = self.mono_font();
+        font.character_size.width + font.character_spacing
+    }
+
+    /// Vertical distance between the top of two lines
+    pub fn line_height(self) -> u32 {
+        self.mono_font().character_size.height
+    }
+
+    /// Width of a line of `characters` characters
+    pub fn width(self, characters: usize) -> u32 {
+        match characters as u32 {
+            0 => 0,
+            n => n * self.character_width() - self.mono_font().character_spacing,
+        }
+    }
+
+    /// Size of a single line of text
+    pub fn measure(self, text: &str) -> Size {
+        Size::new(self.width(text.chars().count()), self.line_height())
+    }
+}
+
+/// Horizontal alignment of each line
+#[derive(Clone, Copy, PartialEq)]
+pub enum Alignment {
+    Left,
+    Centre,
+    Right,
+}
+
+/// Appearance of the text in a [`TextBox`]
+#[derive(Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    pub font: Font,
+    pub foreground: Palette8,
+    /// Colour filling the whole box before drawing, transparent if `None`
+    pub background: Option<Palette8>,
+    pub alignment: Alignment,
+    /// End the last line with `...` when the text does not fit
+    pub ellipsis: bool,
+}
+
+impl TextStyle {
+    /// Left aligned text without background and ellipsis
+    pub fn new(font: Font, foreground: Palette8) -> Self {
+        Self {
+            font,
+            foreground,
+            background: None,
+            alignment: Alignment::Left,
+            ellipsis: false,
+        }
+    }
+
+    pub fn with_background(self, background: Palette8) -> Self {
+        Self {
+            background: Some(background),
+            ..self
+        }
+    }
+
+    pub fn with_alignment(self, alignment: Alignment) -> Self {
+        Self { alignment, ..self }
+    }
+
+    pub fn with_ellipsis(self) -> Self {
+        Self {
+            ellipsis: true,
+            ..self
+        }
+    }
+
+    fn character_style(&self) -> MonoTextStyle<'static, Palette8> {
+        MonoTextStyle::new(self.font.mono_font(), self.foreground)
+    }
+}
+
+const ELLIPSIS: &str = "...";
+
+/// Split off the first line of at most `max` characters, returns the line
+/// without trailing spaces and the remaining text
+fn wrap(text: &str, max: usize) -> (&str, &str) {
+    let mut space = None;
+    for (count, (i, c)) in text.char_indices().enumerate() {
+        if c == '\n' {
+            return (text[..i].trim_end(), &text[i + 1..]);
+        }
+        if count == max {
+            let (line, rest) = if c == ' ' {
+                (&text[..i], &text[i..])
+            } else if let Some(space) = space {
+                (&text[..space], &text[space..])
+            } else {
+                (&text[..i], &text[i..])
+            };
+            return (line.trim_end(), rest.trim_start_matches(' '));
+        }
+        if c == ' ' {
+            space = Some(i);
+        }
+    }
+    (text.trim_end(), "")
+}
+
+/// Text wrapped inside a rectangle
+pub struct TextBox<'a> {
+    text: &'a str,
+    area: Rectangle,
+    style: TextStyle,
+}
+
+impl<'a> TextBox<'a> {
+    pub fn new(text: &'a str, area: Rectangle, style: TextStyle) -> Self {
+        Self { text, area, style }
+    }
+
+    /// Number of characters fitting a line
+    pub fn columns(&self) -> usize {
+        (self.area.size.width / self.style.font.character_width()) as usize
+    }
+
+    /// Number of lines fitting the box
+    pub fn rows(&self) -> usize {
+        (self.area.size.height / self.style.font.line_height()) as usize
+    }
+
+    /// Position of a line of `width` pixels, aligned in the box
+    fn line_position(&self, row: usize, width: u32) -> Point {
+        let space = self.area.size.width.saturating_sub(width) as i32;
+        let x = match self.style.alignment {
+            Alignment::Left => 0,
+            Alignment::Centre => space / 2,
+            Alignment::Right => space,
+        };
+        let y = row as i32 * self.style.font.line_height() as i32;
+        self.area.top_left + Point::new(x, y)
+    }
+
+    fn draw_line<D>(&self, row: usize, parts: &[&str], target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        let characters: usize = parts.iter().map(|part| part.chars().count()).sum();
+        let mut position = self.line_position(row, self.style.font.width(characters));
+        let style = self.style.character_style();
+        for part in parts.iter() {
+            position = Text::with_baseline(part, position, style, Baseline::Top).draw(target)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Drawable for TextBox<'a> {
+    type Color = Palette8;
+    /// The text not drawn
+    type Output = &'a str;
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        if let Some(colour) = self.style.background {
+            target.fill_solid(&self.area, colour)?;
+        }
+        let (columns, rows) = (self.columns(), self.rows());
+        if columns == 0 {
+            return Ok(self.text);
+        }
+        let mut text = self.text;
+        for row in 0..rows {
+            if text.is_empty() {
+                break;
+            }
+            let (line, rest) = wrap(text, columns);
+            if self.style.ellipsis && row + 1 == rows && !rest.is_empty() {
+                let keep = columns.saturating_sub(ELLIPSIS.len());
+                let end = line.char_indices().nth(keep).map_or(line.len(), |(i, _)| i);
+                let dots = &ELLIPSIS[..core::cmp::min(columns, ELLIPSIS.len())];
+                self.draw_line(row, &[line[..end].trim_end(), dots], target)?;
+            } else {
+                self.draw_line(row, &[line], target)?;
+            }
+            text = rest;
+        }
+        Ok(text)
+    }
+}
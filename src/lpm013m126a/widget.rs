@@ -0,0 +1,1005 @@
+//! # Widgets
+//!
+//! Reusable user interface elements drawn on any `Palette8` draw target,
+//! such as [`Lpm013m126a`](crate::lpm013m126a::Lpm013m126a) or a
+//! [`FrameBuffer`](crate::lpm013m126a::FrameBuffer).
+//!
+//! Each widget tracks whether it has changed since it was last drawn, so
+//! [`draw_dirty`] only redraws the changed widgets. A [`Stack`] places
+//! widgets in a column or a row.
+//!
+//! With only the A and B buttons available, [`Focus`] moves the focus to the
+//! next focusable widget on A and activates the focused widget on B. A
+//! checkbox toggles, a button registers a press and a list selects its next
+//! item.
+
+use crate::lpm013m126a::{Alignment, Font, Palette8, TextBox, TextStyle};
+use core::fmt::{self, Write};
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    primitives::{Primitive, PrimitiveStyle, Rectangle},
+    Drawable, Pixel,
+};
+
+/// Colours and font shared by the widgets
+#[derive(Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub font: Font,
+    pub foreground: Palette8,
+    pub background: Palette8,
+    /// Colour of focus, selection and progress
+    pub accent: Palette8,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            font: Font::Medium,
+            foreground: Palette8::White,
+            background: Palette8::Black,
+            accent: Palette8::Cyan,
+        }
+    }
+}
+
+/// State common to all widgets
+pub struct State {
+    pub(crate) bounds: Rectangle,
+    pub(crate) dirty: bool,
+    pub(crate) focused: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            bounds: Rectangle::zero(),
+            dirty: true,
+            focused: false,
+        }
+    }
+}
+
+impl State {
+    pub fn bounds(&self) -> Rectangle {
+        self.bounds
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+}
+
+/// User interface element drawn on `D`
+pub trait Widget<D>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn state(&self) -> &State;
+
+    fn state_mut(&mut self) -> &mut State;
+
+    /// Size wanted by the widget, zero to share the remaining space of a
+    /// [`Stack`]
+    fn preferred_size(&self, theme: &Theme) -> Size;
+
+    /// Draw the whole widget within its bounds
+    fn draw_widget(&self, target: &mut D, theme: &Theme) -> Result<(), D::Error>;
+
+    fn is_focusable(&self) -> bool {
+        false
+    }
+
+    /// Handle the activation of a focused widget
+    fn activate(&mut self) {}
+
+    fn bounds(&self) -> Rectangle {
+        self.state().bounds
+    }
+
+    /// Move the widget, used by layouts
+    fn set_bounds(&mut self, bounds: Rectangle) {
+        let state = self.state_mut();
+        if state.bounds != bounds {
+            state.bounds = bounds;
+            state.dirty = true;
+        }
+    }
+
+    /// Has the widget changed since it was last drawn?
+    fn is_dirty(&self) -> bool {
+        self.state().dirty
+    }
+
+    /// Draw the widget on the next [`draw_dirty`]
+    fn mark_dirty(&mut self) {
+        self.state_mut().dirty = true;
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        let state = self.state_mut();
+        if state.focused != focused {
+            state.focused = focused;
+            state.dirty = true;
+        }
+    }
+
+    /// Draw the widget and clear the dirty state
+    fn draw(&mut self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        self.draw_widget(target, theme)?;
+        self.state_mut().dirty = false;
+        Ok(())
+    }
+}
+
+/// Draw the widgets changed since they were last drawn, returns `true` if
+/// any widget was drawn
+pub fn draw_dirty<D>(
+    widgets: &mut [&mut dyn Widget<D>],
+    target: &mut D,
+    theme: &Theme,
+) -> Result<bool, D::Error>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    let mut drawn = false;
+    for widget in widgets.iter_mut() {
+        if widget.is_dirty() {
+            widget.draw(target, theme)?;
+            drawn = true;
+        }
+    }
+    Ok(drawn)
+}
+
+/// Rectangle shrunk by `inset` pixels on each side
+fn inset(rectangle: Rectangle, inset: u32) -> Rectangle {
+    Rectangle::new(
+        rectangle.top_left + Point::new(inset as i32, inset as i32),
+        Size::new(
+            rectangle.size.width.saturating_sub(2 * inset),
+            rectangle.size.height.saturating_sub(2 * inset),
+        ),
+    )
+}
+
+fn fill<D>(target: &mut D, area: Rectangle, colour: Palette8) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    target.fill_solid(&area, colour)
+}
+
+fn outline<D>(target: &mut D, area: Rectangle, colour: Palette8) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    area.into_styled(PrimitiveStyle::with_stroke(colour, 1))
+        .draw(target)
+}
+
+/// Draw a single line of text in `area`, truncated with ellipsis
+fn text<D>(
+    target: &mut D,
+    area: Rectangle,
+    text: &str,
+    theme: &Theme,
+    colour: Palette8,
+    alignment: Alignment,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    // Centre the line vertically
+    let height = theme.font.line_height();
+    let offset = area.size.height.saturating_sub(height) / 2;
+    let line = Rectangle::new(
+        area.top_left + Point::new(0, offset as i32),
+        Size::new(area.size.width, height),
+    );
+    let style = TextStyle::new(theme.font, colour)
+        .with_alignment(alignment)
+        .with_ellipsis();
+    TextBox::new(text, line, style).draw(target).map(|_| ())
+}
+
+/// Height of a widget holding a single line of text
+fn line_height(theme: &Theme) -> u32 {
+    theme.font.line_height() + 4
+}
+
+/// Text formatted into a fixed size buffer, truncated when full
+pub(crate) struct FormatBuffer<const N: usize> {
+    data: [u8; N],
+    length: usize,
+}
+
+impl<const N: usize> FormatBuffer<N> {
+    pub(crate) fn new() -> Self {
+        Self {
+            data: [0; N],
+            length: 0,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.data[..self.length]).unwrap_or("")
+    }
+}
+
+impl<const N: usize> Write for FormatBuffer<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.length + s.len();
+        if end > N {
+            return Err(fmt::Error);
+        }
+        self.data[self.length..end].copy_from_slice(s.as_bytes());
+        self.length = end;
+        Ok(())
+    }
+}
+
+/// Single line of text
+pub struct Label<'a> {
+    state: State,
+    text: &'a str,
+    alignment: Alignment,
+    colour: Option<Palette8>,
+}
+
+impl<'a> Label<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            state: State::default(),
+            text,
+            alignment: Alignment::Left,
+            colour: None,
+        }
+    }
+
+    pub fn with_alignment(self, alignment: Alignment) -> Self {
+        Self { alignment, ..self }
+    }
+
+    /// Use a colour other than the theme foreground
+    pub fn with_colour(self, colour: Palette8) -> Self {
+        Self {
+            colour: Some(colour),
+            ..self
+        }
+    }
+
+    pub fn set_text(&mut self, text: &'a str) {
+        if self.text != text {
+            self.text = text;
+            self.state.dirty = true;
+        }
+    }
+}
+
+impl<'a, D> Widget<D> for Label<'a>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn state(&self) -> &State {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    fn preferred_size(&self, theme: &Theme) -> Size {
+        Size::new(theme.font.measure(self.text).width + 4, line_height(theme))
+    }
+
+    fn draw_widget(&self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = self.state.bounds;
+        fill(target, bounds, theme.background)?;
+        let colour = self.colour.unwrap_or(theme.foreground);
+        text(
+            target,
+            inset(bounds, 2),
+            self.text,
+            theme,
+            colour,
+            self.alignment,
+        )
+    }
+}
+
+/// Named value with a unit, such as `Temperature 21.5 C`
+///
+/// The value is a fixed point number with `decimals` decimals, at most
+/// [`Value::MAX_DECIMALS`] as an `i32` has no more digits.
+pub struct Value<'a> {
+    state: State,
+    label: &'a str,
+    unit: &'a str,
+    value: i32,
+    decimals: u8,
+}
+
+impl<'a> Value<'a> {
+    /// Largest number of decimals, larger values are clamped
+    pub const MAX_DECIMALS: u8 = 10;
+
+    pub fn new(label: &'a str, unit: &'a str, decimals: u8) -> Self {
+        Self {
+            state: State::default(),
+            label,
+            unit,
+            value: 0,
+            decimals: core::cmp::min(decimals, Self::MAX_DECIMALS),
+        }
+    }
+
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    pub fn set_value(&mut self, value: i32) {
+        if self.value != value {
+            self.value = value;
+            self.state.dirty = true;
+        }
+    }
+
+    fn format(&self) -> FormatBuffer<24> {
+        let mut buffer = FormatBuffer::new();
+        let divisor = 10i64.pow(self.decimals as u32);
+        let value = self.value as i64;
+        let sign = if value < 0 { "-" } else { "" };
+        let _ = if self.decimals == 0 {
+            write!(buffer, "{}{}", value, self.unit)
+        } else {
+            write!(
+                buffer,
+                "{}{}.{:0width$}{}",
+                sign,
+                value.abs() / divisor,
+                value.abs() % divisor,
+                self.unit,
+                width = self.decimals as usize
+            )
+        };
+        buffer
+    }
+}
+
+impl<'a, D> Widget<D> for Value<'a>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn state(&self) -> &State {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    fn preferred_size(&self, theme: &Theme) -> Size {
+        let characters = self.label.chars().count() + 1 + self.format().as_str().len();
+        Size::new(theme.font.width(characters) + 4, line_height(theme))
+    }
+
+    fn draw_widget(&self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = self.state.bounds;
+        fill(target, bounds, theme.background)?;
+        let area = inset(bounds, 2);
+        let value = self.format();
+        let value = value.as_str();
+        // The value is right aligned, the label gets the space left of it
+        let value_width = theme.font.width(value.chars().count());
+        let label_area = Rectangle::new(
+            area.top_left,
+            Size::new(
+                area.size
+                    .width
+                    .saturating_sub(value_width + theme.font.character_width()),
+                area.size.height,
+            ),
+        );
+        text(
+            target,
+            label_area,
+            self.label,
+            theme,
+            theme.foreground,
+            Alignment::Left,
+        )?;
+        text(target, area, value, theme, theme.accent, Alignment::Right)
+    }
+}
+
+/// Horizontal bar filled in proportion to a value
+pub struct ProgressBar {
+    state: State,
+    value: u32,
+    maximum: u32,
+}
+
+impl ProgressBar {
+    pub fn new(maximum: u32) -> Self {
+        Self {
+            state: State::default(),
+            value: 0,
+            maximum,
+        }
+    }
+
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Set the value, limited to the maximum
+    pub fn set_value(&mut self, value: u32) {
+        let value = core::cmp::min(value, self.maximum);
+        if self.value != value {
+            self.value = value;
+            self.state.dirty = true;
+        }
+    }
+}
+
+impl<D> Widget<D> for ProgressBar
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn state(&self) -> &State {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    fn preferred_size(&self, _theme: &Theme) -> Size {
+        Size::new(0, 12)
+    }
+
+    fn draw_widget(&self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = inset(self.state.bounds, 1);
+        outline(target, bounds, theme.foreground)?;
+        let inner = inset(bounds, 2);
+        let filled = match self.maximum {
+            0 => 0,
+            maximum => (inner.size.width as u64 * self.value as u64 / maximum as u64) as u32,
+        };
+        let (done, left) = (
+            Rectangle::new(inner.top_left, Size::new(filled, inner.size.height)),
+            Rectangle::new(
+                inner.top_left + Point::new(filled as i32, 0),
+                Size::new(inner.size.width - filled, inner.size.height),
+            ),
+        );
+        // Clear the gap between outline and bar as well
+        outline(target, inset(bounds, 1), theme.background)?;
+        fill(target, done, theme.accent)?;
+        fill(target, left, theme.background)
+    }
+}
+
+/// Monochrome bitmap, rows of `width` bits padded to whole octets with the
+/// first pixel in the most significant bit
+#[derive(Clone, Copy)]
+pub struct Icon<'a> {
+    width: u32,
+    height: u32,
+    data: &'a [u8],
+}
+
+impl<'a> Icon<'a> {
+    pub const fn new(width: u32, height: u32, data: &'a [u8]) -> Self {
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    /// Draw the set pixels in `colour`, other pixels are left as is
+    pub fn draw<D>(&self, target: &mut D, top_left: Point, colour: Palette8) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        let stride = (self.width as usize + 7) / 8;
+        let pixels = (0..self.height).flat_map(move |y| {
+            (0..self.width).filter_map(move |x| {
+                let octet = self.data.get(y as usize * stride + x as usize / 8)?;
+                if octet & (0x80 >> (x % 8)) != 0 {
+                    Some(Pixel(top_left + Point::new(x as i32, y as i32), colour))
+                } else {
+                    None
+                }
+            })
+        });
+        target.draw_iter(pixels)
+    }
+}
+
+/// Button with an icon and a label
+pub struct IconButton<'a> {
+    state: State,
+    icon: Icon<'a>,
+    label: &'a str,
+    pressed: bool,
+}
+
+impl<'a> IconButton<'a> {
+    pub fn new(icon: Icon<'a>, label: &'a str) -> Self {
+        Self {
+            state: State::default(),
+            icon,
+            label,
+            pressed: false,
+        }
+    }
+
+    /// Has the button been activated since the last call?
+    pub fn take_pressed(&mut self) -> bool {
+        core::mem::replace(&mut self.pressed, false)
+    }
+}
+
+impl<'a, D> Widget<D> for IconButton<'a>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn state(&self) -> &State {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    fn preferred_size(&self, theme: &Theme) -> Size {
+        let icon = self.icon.size();
+        Size::new(
+            icon.width + theme.font.measure(self.label).width + 12,
+            core::cmp::max(icon.height + 6, line_height(theme)),
+        )
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn activate(&mut self) {
+        self.pressed = true;
+    }
+
+    fn draw_widget(&self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = self.state.bounds;
+        fill(target, bounds, theme.background)?;
+        let border = if self.state.focused {
+            theme.accent
+        } else {
+            theme.foreground
+        };
+        outline(target, bounds, border)?;
+        let area = inset(bounds, 3);
+        let icon = self.icon.size();
+        let top = area.size.height.saturating_sub(icon.height) / 2;
+        self.icon.draw(
+            target,
+            area.top_left + Point::new(0, top as i32),
+            theme.foreground,
+        )?;
+        let label = Rectangle::new(
+            area.top_left + Point::new(icon.width as i32 + 3, 0),
+            Size::new(
+                area.size.width.saturating_sub(icon.width + 3),
+                area.size.height,
+            ),
+        );
+        text(
+            target,
+            label,
+            self.label,
+            theme,
+            theme.foreground,
+            Alignment::Left,
+        )
+    }
+}
+
+/// Labelled box toggled on activation
+pub struct Checkbox<'a> {
+    state: State,
+    label: &'a str,
+    checked: bool,
+}
+
+impl<'a> Checkbox<'a> {
+    pub fn new(label: &'a str, checked: bool) -> Self {
+        Self {
+            state: State::default(),
+            label,
+            checked,
+        }
+    }
+
+    pub fn is_checked(&self) -> bool {
+        self.checked
+    }
+
+    pub fn set_checked(&mut self, checked: bool) {
+        if self.checked != checked {
+            self.checked = checked;
+            self.state.dirty = true;
+        }
+    }
+}
+
+impl<'a, D> Widget<D> for Checkbox<'a>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn state(&self) -> &State {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    fn preferred_size(&self, theme: &Theme) -> Size {
+        let height = line_height(theme);
+        Size::new(height + theme.font.measure(self.label).width + 4, height)
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn activate(&mut self) {
+        self.checked = !self.checked;
+        self.state.dirty = true;
+    }
+
+    fn draw_widget(&self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = self.state.bounds;
+        fill(target, bounds, theme.background)?;
+        let side = core::cmp::min(bounds.size.height, bounds.size.width);
+        let check = inset(Rectangle::new(bounds.top_left, Size::new(side, side)), 2);
+        let border = if self.state.focused {
+            theme.accent
+        } else {
+            theme.foreground
+        };
+        outline(target, check, border)?;
+        if self.checked {
+            fill(target, inset(check, 2), theme.accent)?;
+        }
+        let label = Rectangle::new(
+            bounds.top_left + Point::new(side as i32 + 2, 0),
+            Size::new(bounds.size.width.saturating_sub(side + 2), side),
+        );
+        text(
+            target,
+            label,
+            self.label,
+            theme,
+            theme.foreground,
+            Alignment::Left,
+        )
+    }
+}
+
+/// List of items with one selected item, scrolled to keep the selection
+/// visible
+pub struct List<'a> {
+    state: State,
+    items: &'a [&'a str],
+    selected: usize,
+}
+
+impl<'a> List<'a> {
+    pub fn new(items: &'a [&'a str]) -> Self {
+        Self {
+            state: State::default(),
+            items,
+            selected: 0,
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_item(&self) -> Option<&'a str> {
+        self.items.get(self.selected).copied()
+    }
+
+    /// Select an item, indices past the end are ignored
+    pub fn set_selected(&mut self, selected: usize) {
+        if selected < self.items.len() && self.selected != selected {
+            self.selected = selected;
+            self.state.dirty = true;
+        }
+    }
+}
+
+impl<'a, D> Widget<D> for List<'a>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn state(&self) -> &State {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    fn preferred_size(&self, _theme: &Theme) -> Size {
+        Size::zero()
+    }
+
+    fn is_focusable(&self) -> bool {
+        !self.items.is_empty()
+    }
+
+    /// Select the next item, wrapping to the first
+    fn activate(&mut self) {
+        if !self.items.is_empty() {
+            self.selected = (self.selected + 1) % self.items.len();
+            self.state.dirty = true;
+        }
+    }
+
+    fn draw_widget(&self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = self.state.bounds;
+        fill(target, bounds, theme.background)?;
+        let border = if self.state.focused {
+            theme.accent
+        } else {
+            theme.foreground
+        };
+        outline(target, bounds, border)?;
+        let area = inset(bounds, 2);
+        let height = theme.font.line_height() + 2;
+        let rows = (area.size.height / height) as usize;
+        if rows == 0 {
+            return Ok(());
+        }
+        let first = (self.selected + 1).saturating_sub(rows);
+        for (row, (index, item)) in self
+            .items
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows)
+            .enumerate()
+        {
+            let line = Rectangle::new(
+                area.top_left + Point::new(0, (row as u32 * height) as i32),
+                Size::new(area.size.width, height),
+            );
+            let colour = if index == self.selected {
+                fill(target, line, theme.accent)?;
+                theme.background
+            } else {
+                theme.foreground
+            };
+            text(target, inset(line, 1), item, theme, colour, Alignment::Left)?;
+        }
+        Ok(())
+    }
+}
+
+/// Bar with a text at each end, in inverted colours
+pub struct StatusBar<'a> {
+    state: State,
+    left: &'a str,
+    right: &'a str,
+}
+
+impl<'a> StatusBar<'a> {
+    pub fn new(left: &'a str, right: &'a str) -> Self {
+        Self {
+            state: State::default(),
+            left,
+            right,
+        }
+    }
+
+    pub fn set_left(&mut self, left: &'a str) {
+        if self.left != left {
+            self.left = left;
+            self.state.dirty = true;
+        }
+    }
+
+    pub fn set_right(&mut self, right: &'a str) {
+        if self.right != right {
+            self.right = right;
+            self.state.dirty = true;
+        }
+    }
+}
+
+impl<'a, D> Widget<D> for StatusBar<'a>
+where
+    D: DrawTarget<Color = Palette8>,
+{
+    fn state(&self) -> &State {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    fn preferred_size(&self, theme: &Theme) -> Size {
+        Size::new(0, line_height(theme))
+    }
+
+    fn draw_widget(&self, target: &mut D, theme: &Theme) -> Result<(), D::Error> {
+        let bounds = self.state.bounds;
+        fill(target, bounds, theme.foreground)?;
+        let area = inset(bounds, 2);
+        text(
+            target,
+            area,
+            self.right,
+            theme,
+            theme.background,
+            Alignment::Right,
+        )?;
+        let right = theme.font.width(self.right.chars().count());
+        let left = Rectangle::new(
+            area.top_left,
+            Size::new(
+                area.size
+                    .width
+                    .saturating_sub(right + theme.font.character_width()),
+                area.size.height,
+            ),
+        );
+        text(
+            target,
+            left,
+            self.left,
+            theme,
+            theme.background,
+            Alignment::Left,
+        )
+    }
+}
+
+/// Direction widgets are placed in by a [`Stack`]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// Top to bottom
+    Column,
+    /// Left to right
+    Row,
+}
+
+/// Layout placing widgets after each other
+///
+/// Each widget gets its preferred length in the direction of the stack and
+/// the whole area across it. Widgets preferring zero length share the
+/// remaining space.
+pub struct Stack {
+    area: Rectangle,
+    direction: Direction,
+    spacing: u32,
+}
+
+impl Stack {
+    pub fn new(area: Rectangle, direction: Direction) -> Self {
+        Self {
+            area,
+            direction,
+            spacing: 0,
+        }
+    }
+
+    /// Pixels between the widgets
+    pub fn with_spacing(self, spacing: u32) -> Self {
+        Self { spacing, ..self }
+    }
+
+    /// Length along and across the stack
+    fn split(&self, size: Size) -> (u32, u32) {
+        match self.direction {
+            Direction::Column => (size.height, size.width),
+            Direction::Row => (size.width, size.height),
+        }
+    }
+
+    /// Set the bounds of the widgets
+    pub fn arrange<D>(&self, widgets: &mut [&mut dyn Widget<D>], theme: &Theme)
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        let (length, across) = self.split(self.area.size);
+        let mut fixed = 0;
+        let mut shared = 0;
+        for widget in widgets.iter() {
+            match self.split(widget.preferred_size(theme)).0 {
+                0 => shared += 1,
+                n => fixed += n,
+            }
+        }
+        let spacing = self.spacing * (widgets.len() as u32).saturating_sub(1);
+        let remaining = length.saturating_sub(fixed + spacing);
+        let share = if shared > 0 { remaining / shared } else { 0 };
+        let mut position = 0;
+        for widget in widgets.iter_mut() {
+            let size = match self.split(widget.preferred_size(theme)).0 {
+                0 => share,
+                n => n,
+            };
+            let (offset, size) = match self.direction {
+                Direction::Column => (Point::new(0, position as i32), Size::new(across, size)),
+                Direction::Row => (Point::new(position as i32, 0), Size::new(size, across)),
+            };
+            widget.set_bounds(Rectangle::new(self.area.top_left + offset, size));
+            position += self.split(size).0 + self.spacing;
+        }
+    }
+}
+
+/// Focus navigation using the A and B buttons
+#[derive(Default)]
+pub struct Focus {
+    index: Option<usize>,
+}
+
+impl Focus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index of the focused widget
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    /// Move the focus to the next focusable widget, wrapping to the first,
+    /// used for button A
+    pub fn next<D>(&mut self, widgets: &mut [&mut dyn Widget<D>])
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        let count = widgets.len();
+        let start = self.index.map_or(0, |index| index + 1);
+        let next = (0..count)
+            .map(|i| (start + i) % count)
+            .find(|&i| widgets[i].is_focusable());
+        if let Some(index) = self.index {
+            if let Some(widget) = widgets.get_mut(index) {
+                widget.set_focused(false);
+            }
+        }
+        if let Some(index) = next {
+            widgets[index].set_focused(true);
+        }
+        self.index = next;
+    }
+
+    /// Activate the focused widget, used for button B, returns the index of
+    /// the activated widget
+    pub fn activate<D>(&mut self, widgets: &mut [&mut dyn Widget<D>]) -> Option<usize>
+    where
+        D: DrawTarget<Color = Palette8>,
+    {
+        let index = self.index?;
+        widgets.get_mut(index)?.activate();
+        Some(index)
+    }
+}
@@ -15,6 +15,7 @@ use core::iter::repeat_with;
 use crate::hal::gpio::{Floating, Input, Output, Pin, PushPull};
 use crate::hal::target_constants::{EASY_DMA_SIZE, FORCE_COPY_BUFFER_SIZE};
 use crate::{slice_in_ram, slice_in_ram_or, DmaSlice};
+use embedded_dma::{ReadBuffer, WriteBuffer};
 use embedded_hal::digital::v2::OutputPin;
 
 /// Interface to a SPIM instance.
@@ -168,13 +169,22 @@ where
     }
     /// Internal helper function to setup and execute SPIM DMA transfer.
     fn do_spi_dma_transfer(&mut self, tx: DmaSlice, rx: DmaSlice) -> Result<(), Error> {
-        self.start_spi_dma_transfer(tx, rx)?;
-        self.clear_write_event();
+        // The slices are borrowed until the transfer has ended
+        unsafe { self.start_spi_dma_transfer(tx, rx)? };
+        self.wait_spi_dma_transfer();
         Ok(())
     }
 
-    /// Internal helper function to setup and execute SPIM DMA transfer.
-    pub(crate) fn start_spi_dma_transfer(
+    /// Internal helper function to setup and start SPIM DMA transfer.
+    ///
+    /// Returns without waiting for the transfer.
+    ///
+    /// # Safety
+    ///
+    /// The buffers must be in RAM and must neither move nor be freed until
+    /// the END event, `tx` must not be written and `rx` must not be accessed
+    /// until then.
+    pub(crate) unsafe fn start_spi_dma_transfer(
         &mut self,
         tx: DmaSlice,
         rx: DmaSlice,
@@ -208,35 +218,37 @@ where
         self.0.tasks_start.write(|w|
             // `1` is a valid value to write to task registers.
             unsafe { w.bits(1) });
+        Ok(())
+    }
 
-        // Conservative compiler fence to prevent optimizations that do not
-        // take in to account actions by DMA. The fence has been placed here,
-        // after all possible DMA actions have completed.
-        compiler_fence(SeqCst);
+    /// Has the transfer ended? The END event is set until cleared.
+    pub(crate) fn is_spi_dma_transfer_done(&self) -> bool {
+        self.0.events_end.read().bits() != 0
+    }
 
+    /// Wait for the transfer to end and clear the END event.
+    pub(crate) fn wait_spi_dma_transfer(&mut self) {
         // Wait for END event.
         //
         // This event is triggered once both transmitting and receiving are
         // done.
-        while self.0.events_end.read().bits() == 0 {}
-
-        // Reset the event, otherwise it will always read `1` from now on.
-        self.0.events_end.write(|w| w);
+        while !self.is_spi_dma_transfer_done() {}
+        self.clear_write_event();
+    }
 
-        // Conservative compiler fence to prevent optimizations that do not
-        // take in to account actions by DMA. The fence has been placed here,
-        // after all possible DMA actions have completed.
-        compiler_fence(SeqCst);
-        Ok(())
+    /// Stop a transfer which has not ended, waiting for it to stop, and
+    /// clear the END event.
+    pub(crate) fn stop_spi_dma_transfer(&mut self) {
+        if !self.is_spi_dma_transfer_done() {
+            self.0.tasks_stop.write(|w| unsafe { w.bits(1) });
+            // A transfer ending while stopped sets END instead of STOPPED
+            while !self.is_spi_dma_transfer_done() && self.0.events_stopped.read().bits() == 0 {}
+            self.0.events_stopped.write(|w| w);
+        }
+        self.clear_write_event();
     }
 
     pub fn clear_write_event(&mut self) {
-        // Wait for END event.
-        //
-        // This event is triggered once both transmitting and receiving are
-        // done.
-        // while self.0.events_end.read().bits() == 0 {}
-
         // Reset the event, otherwise it will always read `1` from now on.
         self.0.events_end.write(|w| w);
 
@@ -246,6 +258,60 @@ where
         compiler_fence(SeqCst);
     }
 
+    /// Start a transfer sending `tx` while receiving into `rx`, without
+    /// waiting for it to end.
+    ///
+    /// The transfer owns the SPIM and the buffers until it has ended, the
+    /// END event raises the SPIM interrupt. The `'static` buffers of
+    /// `embedded-dma` can neither move nor be freed while owned by the
+    /// transfer. Both buffers must be in RAM and at most `EASY_DMA_SIZE`
+    /// octets. Chip select is handled by the peripheral, as configured by
+    /// `Pins::csn`.
+    pub fn dma_transfer<TX, RX>(
+        self,
+        tx: TX,
+        mut rx: RX,
+    ) -> Result<Transfer<T, TX, RX>, (Error, Self, TX, RX)>
+    where
+        TX: ReadBuffer<Word = u8>,
+        RX: WriteBuffer<Word = u8>,
+    {
+        let tx_slice = match dma_read_slice(&tx) {
+            Ok(slice) => slice,
+            Err(e) => return Err((e, self, tx, rx)),
+        };
+        let (ptr, len) = unsafe { rx.write_buffer() };
+        if len > EASY_DMA_SIZE {
+            return Err((Error::RxBufferTooLong, self, tx, rx));
+        }
+        let rx_slice = DmaSlice::from_slice(unsafe { core::slice::from_raw_parts(ptr, len) });
+        let mut spim = self;
+        // The buffers are owned by the transfer until it has ended
+        match unsafe { spim.start_spi_dma_transfer(tx_slice, rx_slice) } {
+            Ok(()) => Ok(Transfer::new(spim, tx, rx)),
+            Err(e) => Err((e, spim, tx, rx)),
+        }
+    }
+
+    /// Start a transfer sending `tx`, without waiting for it to end.
+    ///
+    /// See [`Spim::dma_transfer`].
+    pub fn dma_write<TX>(self, tx: TX) -> Result<Transfer<T, TX, ()>, (Error, Self, TX)>
+    where
+        TX: ReadBuffer<Word = u8>,
+    {
+        let tx_slice = match dma_read_slice(&tx) {
+            Ok(slice) => slice,
+            Err(e) => return Err((e, self, tx)),
+        };
+        let mut spim = self;
+        // The buffer is owned by the transfer until it has ended
+        match unsafe { spim.start_spi_dma_transfer(tx_slice, DmaSlice::null()) } {
+            Ok(()) => Ok(Transfer::new(spim, tx, ())),
+            Err(e) => Err((e, spim, tx)),
+        }
+    }
+
     /// Read and write from a SPI slave, using a single buffer.
     ///
     /// This method implements a complete read transaction, which consists of
@@ -386,12 +452,93 @@ where
         self.transfer_split_uneven(chip_select, tx_buffer, &mut [0u8; 0])
     }
 
+    /// Enable the SPIM instance after it was disabled.
+    pub fn enable(&mut self) {
+        self.0.enable.write(|w| w.enable().enabled());
+    }
+
+    /// Disable the SPIM instance to save power.
+    ///
+    /// The configuration is kept, a transfer must not be in progress.
+    pub fn disable(&mut self) {
+        self.0.enable.write(|w| w.enable().disabled());
+    }
+
     /// Return the raw interface to the underlying SPIM peripheral.
     pub fn free(self) -> T {
         self.0
     }
 }
 
+/// Slice of a buffer to send, which must be in RAM and fit one transfer
+fn dma_read_slice<B: ReadBuffer<Word = u8>>(buffer: &B) -> Result<DmaSlice, Error> {
+    let (ptr, len) = unsafe { buffer.read_buffer() };
+    let slice = unsafe { core::slice::from_raw_parts(ptr, len) };
+    if len > EASY_DMA_SIZE {
+        return Err(Error::TxBufferTooLong);
+    }
+    slice_in_ram_or(slice, Error::DMABufferNotInDataMemory)?;
+    Ok(DmaSlice::from_slice(slice))
+}
+
+/// A DMA transfer in progress, started by [`Spim::dma_transfer`] or
+/// [`Spim::dma_write`].
+///
+/// The END event stays set once the transfer has ended, so the SPIM
+/// interrupt keeps occurring until the event is cleared by `wait()`. A
+/// transfer dropped before it has ended is stopped, as the buffers are no
+/// longer owned.
+pub struct Transfer<T: Instance, TX, RX> {
+    inner: Option<Inner<T, TX, RX>>,
+}
+
+struct Inner<T, TX, RX> {
+    spim: Spim<T>,
+    tx: TX,
+    rx: RX,
+}
+
+impl<T, TX, RX> Transfer<T, TX, RX>
+where
+    T: Instance,
+{
+    fn new(spim: Spim<T>, tx: TX, rx: RX) -> Self {
+        Self {
+            inner: Some(Inner { spim, tx, rx }),
+        }
+    }
+
+    /// Has the transfer ended? Check this when the SPIM interrupt occurs.
+    pub fn is_done(&self) -> bool {
+        match &self.inner {
+            Some(inner) => inner.spim.is_spi_dma_transfer_done(),
+            None => true,
+        }
+    }
+
+    /// Wait for the transfer to end, clear the END event and return the SPIM
+    /// and the buffers.
+    ///
+    /// Returns at once after `is_done()` returned `true`.
+    pub fn wait(mut self) -> (Spim<T>, TX, RX) {
+        // Only taken here or when dropped
+        let mut inner = self.inner.take().unwrap();
+        inner.spim.wait_spi_dma_transfer();
+        (inner.spim, inner.tx, inner.rx)
+    }
+}
+
+impl<T, TX, RX> Drop for Transfer<T, TX, RX>
+where
+    T: Instance,
+{
+    fn drop(&mut self) {
+        if let Some(inner) = &mut self.inner {
+            inner.spim.stop_spi_dma_transfer();
+        }
+    }
+}
+
 /// GPIO pins for SPIM interface
 pub struct Pins {
     /// SPI clock
@@ -0,0 +1,111 @@
+//! # Calendar date and time of day
+//!
+//! [`DateTime`] converts between seconds since 2000-01-01 00:00:00 and the
+//! calendar date and time, without time zones or leap seconds. A `u32` count
+//! of seconds lasts until the year 2136.
+
+const SECONDS_PER_DAY: u32 = 86_400;
+// Days in 400 years of the Gregorian calendar
+const DAYS_PER_ERA: i32 = 146_097;
+// Days from 2000-01-01 to 2000-03-01, years are counted from March
+const MARCH_FIRST: i32 = 60;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Date and time of day
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub year: u16,
+    /// Month of the year, 1 to 12
+    pub month: u8,
+    /// Day of the month, 1 to 31
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Self {
+        Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    /// Date and time `seconds` after 2000-01-01 00:00:00
+    pub fn from_seconds(seconds: u32) -> Self {
+        let time = seconds % SECONDS_PER_DAY;
+        // Days since 2000-03-01, in 400 year eras
+        let days = (seconds / SECONDS_PER_DAY) as i32 - MARCH_FIRST;
+        let era = days.div_euclid(DAYS_PER_ERA);
+        let day_of_era = days.rem_euclid(DAYS_PER_ERA);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+            - day_of_era / (DAYS_PER_ERA - 1))
+            / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        // Month counted from March
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = 2000 + era * 400 + year_of_era + (month <= 2) as i32;
+        Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+        }
+    }
+
+    /// Seconds since 2000-01-01 00:00:00
+    pub fn to_seconds(&self) -> u32 {
+        let month = self.month as i32;
+        let year = self.year as i32 - 2000 - (month <= 2) as i32;
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = if month > 2 { month - 3 } else { month + 9 };
+        let day_of_year = (153 * month + 2) / 5 + self.day as i32 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * DAYS_PER_ERA + day_of_era + MARCH_FIRST;
+        days as u32 * SECONDS_PER_DAY
+            + self.hour as u32 * 3600
+            + self.minute as u32 * 60
+            + self.second as u32
+    }
+
+    /// Day of the week, 0 for Monday to 6 for Sunday
+    pub fn weekday(&self) -> u8 {
+        // 2000-01-01 was a Saturday
+        ((self.to_seconds() / SECONDS_PER_DAY + 5) % 7) as u8
+    }
+
+    /// Abbreviated name of the day of the week
+    pub fn weekday_name(&self) -> &'static str {
+        WEEKDAYS[self.weekday() as usize]
+    }
+
+    /// Abbreviated name of the month
+    pub fn month_name(&self) -> &'static str {
+        MONTHS[(self.month.clamp(1, 12) - 1) as usize]
+    }
+
+    /// Is the date the same, ignoring the time of day?
+    pub fn same_date(&self, other: &DateTime) -> bool {
+        (self.year, self.month, self.day) == (other.year, other.month, other.day)
+    }
+}
+
+impl Default for DateTime {
+    fn default() -> Self {
+        Self::new(2000, 1, 1, 0, 0, 0)
+    }
+}
@@ -0,0 +1,107 @@
+//! VCOM toggling for memory in pixel displays without the CPU
+//!
+//! The panel needs the EXTCOMIN pin toggled at a low frequency to avoid
+//! charging the liquid crystal. [`Vcom`] toggles the pin from an RTC compare
+//! event through PPI and GPIOTE, so no interrupt or timer using the high
+//! frequency clock is needed while the screen is static.
+//!
+//! A GPIOTE channel and a configurable PPI channel with fork are used. Start
+//! the low frequency clock before creating `Vcom`, preferably from the RC
+//! oscillator as the synthesized clock keeps the high frequency clock
+//! running.
+//!
+//! ```ignore
+//! let gpiote = Gpiote::new(cx.device.GPIOTE);
+//! let ppi = ppi::Parts::new(cx.device.PPI);
+//! let vcom = Vcom::new(cx.device.RTC1, jdi_com, &gpiote.channel7(), ppi.ppi19, 1)?;
+//! ```
+
+use crate::{
+    hal::{
+        gpiote::{GpioteChannel, GpioteOutputPin, TaskOutPolarity},
+        ppi::{ConfigurablePpi, Ppi},
+        rtc::{Instance, Rtc, RtcCompareReg, RtcInterrupt},
+    },
+    Error,
+};
+
+const LFCLK_HZ: u32 = 32_768;
+// the RTC counter is 24 bits
+const MAX_TICKS: u32 = 0x00ff_ffff;
+
+/// EXTCOMIN toggled by the RTC
+pub struct Vcom<R: Instance, P> {
+    rtc: Rtc<R>,
+    ppi: P,
+}
+
+impl<R, P> Vcom<R, P>
+where
+    R: Instance,
+    P: ConfigurablePpi,
+{
+    /// Toggle `pin` through the GPIOTE `channel` to produce a VCOM signal of
+    /// `frequency` Hz
+    ///
+    /// The RTC counts the low frequency clock without prescaler and must not
+    /// be used for other purposes.
+    pub fn new<PIN>(
+        rtc: R,
+        pin: PIN,
+        channel: &GpioteChannel<'_>,
+        mut ppi: P,
+        frequency: u32,
+    ) -> Result<Self, Error>
+    where
+        PIN: GpioteOutputPin,
+    {
+        channel
+            .output_pin(pin)
+            .task_out_polarity(TaskOutPolarity::Toggle)
+            .init_low();
+
+        // Toggle on compare and restart the count
+        ppi.set_event_endpoint(&rtc.events_compare[0]);
+        ppi.set_task_endpoint(channel.task_out());
+        ppi.set_fork_task_endpoint(&rtc.tasks_clear);
+
+        let mut rtc = Rtc::new(rtc, 0).map_err(|_| Error::UnsupportedError)?;
+        rtc.enable_event(RtcInterrupt::Compare0);
+        ppi.enable();
+
+        let mut vcom = Self { rtc, ppi };
+        vcom.set_frequency(frequency);
+        Ok(vcom)
+    }
+
+    /// Change the VCOM frequency, the pin toggles twice per period
+    pub fn set_frequency(&mut self, frequency: u32) {
+        let ticks = LFCLK_HZ / (2 * frequency.max(1));
+        let ticks = ticks.clamp(1, MAX_TICKS);
+        self.rtc.disable_counter();
+        self.rtc.clear_counter();
+        let _ = self.rtc.set_compare(RtcCompareReg::Compare0, ticks);
+        self.rtc.enable_counter();
+    }
+
+    /// Stop toggling, the pin keeps its level
+    pub fn stop(&mut self) {
+        self.rtc.disable_counter();
+    }
+
+    /// Continue toggling after `stop`
+    pub fn start(&mut self) {
+        self.rtc.enable_counter();
+    }
+
+    /// Stop toggling and release the RTC and the PPI channel
+    ///
+    /// The GPIOTE channel keeps controlling the pin.
+    pub fn release(mut self) -> (R, P) {
+        self.rtc.disable_counter();
+        self.rtc.disable_event(RtcInterrupt::Compare0);
+        self.ppi.disable();
+        self.ppi.clear_fork_task_endpoint();
+        (self.rtc.release(), self.ppi)
+    }
+}
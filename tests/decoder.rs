@@ -0,0 +1,230 @@
+use microbit_two::lpm013m126a::{
+    decoder::{Blink, DecodeError, Transfer},
+    panel, Command, Decoder, FrameBuffer, Palette8, Panel,
+};
+
+/// A test image using every colour
+fn draw<P: Panel, S: AsRef<[u8]> + AsMut<[u8]>>(frame_buffer: &mut FrameBuffer<P, S>) {
+    for y in 0..P::HEIGHT {
+        for x in 0..P::WIDTH {
+            frame_buffer.set_pixel(x, y, Palette8::from(((x / 3 + y / 5) % 8) as u8));
+        }
+    }
+}
+
+/// The line update transfer of a frame buffer without stored commands
+fn line_transfer<P: Panel>(frame_buffer: &FrameBuffer<P>, line: u16) -> Vec<u8> {
+    let mut transfer = P::line_header(line).to_vec();
+    transfer.extend_from_slice(frame_buffer.line(line));
+    transfer.extend_from_slice(&[0, 0]);
+    transfer
+}
+
+/// Send the changed lines as the drivers do, the number of lines sent
+fn send_changed<P: Panel>(frame_buffer: &mut FrameBuffer<P>, decoder: &mut Decoder<P>) -> u32 {
+    let lines = decoder.lines();
+    for line in 0..P::HEIGHT {
+        if frame_buffer.is_line_changed(line) {
+            frame_buffer.mark_sent(line);
+            let transfer = line_transfer(frame_buffer, line);
+            assert_eq!(decoder.decode(&transfer), Ok(Transfer::Line(line)));
+        }
+    }
+    decoder.lines() - lines
+}
+
+fn assert_same<P: Panel, S: AsRef<[u8]> + AsMut<[u8]>>(
+    decoder: &Decoder<P>,
+    frame_buffer: &FrameBuffer<P, S>,
+) {
+    for y in 0..P::HEIGHT {
+        for x in 0..P::WIDTH {
+            assert!(decoder.visible_pixel(x, y) == frame_buffer.read_pixel(x, y));
+        }
+    }
+}
+
+fn frame<P: Panel>() {
+    let mut frame_buffer = FrameBuffer::<P>::new();
+    draw(&mut frame_buffer);
+    let mut decoder = Decoder::<P>::new();
+    for line in 0..P::HEIGHT {
+        let transfer = line_transfer(&frame_buffer, line);
+        assert_eq!(decoder.decode(&transfer), Ok(Transfer::Line(line)));
+    }
+    assert_eq!(decoder.lines(), P::HEIGHT as u32);
+    assert_eq!(decoder.errors(), 0);
+    assert_same(&decoder, &frame_buffer);
+}
+
+fn command_frame<P: Panel>() {
+    let mut frame_buffer = FrameBuffer::<P, _>::with_storage(vec![0u8; P::COMMAND_FRAME_OCTETS])
+        .ok()
+        .unwrap();
+    draw(&mut frame_buffer);
+    let mut decoder = Decoder::<P>::new();
+    for line in (0..P::HEIGHT).rev() {
+        let transfer = frame_buffer.line_command(line).unwrap();
+        assert_eq!(decoder.decode(transfer), Ok(Transfer::Line(line)));
+    }
+    assert_eq!(decoder.errors(), 0);
+    assert_same(&decoder, &frame_buffer);
+}
+
+fn changed_lines<P: Panel>() {
+    let mut frame_buffer = FrameBuffer::<P>::new();
+    let mut decoder = Decoder::<P>::new();
+    draw(&mut frame_buffer);
+    assert_eq!(
+        send_changed(&mut frame_buffer, &mut decoder),
+        P::HEIGHT as u32
+    );
+    assert_eq!(send_changed(&mut frame_buffer, &mut decoder), 0);
+
+    // A single row edit sends a single line
+    frame_buffer.set_pixel(P::WIDTH - 1, P::HEIGHT - 1, Palette8::Red);
+    assert_eq!(send_changed(&mut frame_buffer, &mut decoder), 1);
+    frame_buffer.fill_rect(0, 33, P::WIDTH, 3, Palette8::Blue);
+    assert_eq!(send_changed(&mut frame_buffer, &mut decoder), 3);
+    frame_buffer.line_mut(5)[0] = 0xff;
+    assert_eq!(send_changed(&mut frame_buffer, &mut decoder), 1);
+    assert_same(&decoder, &frame_buffer);
+
+    // Copying marks the lines that differ
+    let mut other = FrameBuffer::<P>::new();
+    other.copy_from(&frame_buffer);
+    while let Some(line) = (0..P::HEIGHT).find(|&line| other.is_line_changed(line)) {
+        other.mark_sent(line);
+    }
+    other.set_pixel(0, 7, Palette8::Green);
+    frame_buffer.copy_from(&other);
+    assert_eq!(send_changed(&mut frame_buffer, &mut decoder), 1);
+    assert_same(&decoder, &frame_buffer);
+}
+
+/// The checks of every panel
+fn check_panel<P: Panel>() {
+    frame::<P>();
+    command_frame::<P>();
+    changed_lines::<P>();
+}
+
+const PANELS: [(&str, fn()); 4] = [
+    ("LPM013M126A", check_panel::<panel::Lpm013m126a>),
+    ("LPM027M128B", check_panel::<panel::Lpm027m128b>),
+    ("LS013B7DH03", check_panel::<panel::Ls013b7dh03>),
+    ("LS027B7DH01", check_panel::<panel::Ls027b7dh01>),
+];
+
+#[test]
+fn panels() {
+    for (name, check) in PANELS.iter() {
+        // shown when the check fails
+        println!("{}", name);
+        check();
+    }
+}
+
+#[test]
+fn commands() {
+    type P = panel::Lpm013m126a;
+    let command = |command| [<P as Panel>::command(command).unwrap(), 0];
+    let mut decoder = Decoder::<P>::new();
+    assert!(decoder.visible_pixel(3, 3) == Palette8::Black);
+    decoder.decode(&command(Command::Clear)).unwrap();
+    assert!(decoder.visible_pixel(3, 3) == Palette8::White);
+
+    decoder.decode(&command(Command::BlinkBlack)).unwrap();
+    assert_eq!(decoder.blink(), Blink::Black);
+    assert!(decoder.visible_pixel(3, 3) == Palette8::Black);
+    decoder.decode(&command(Command::BlinkWhite)).unwrap();
+    assert_eq!(decoder.blink(), Blink::White);
+    decoder.decode(&command(Command::BlinkOff)).unwrap();
+    assert_eq!(decoder.blink(), Blink::Off);
+
+    decoder.decode(&command(Command::InvertOn)).unwrap();
+    assert!(decoder.inverted());
+    assert!(decoder.visible_pixel(3, 3) == Palette8::Black);
+    assert_eq!(
+        decoder.decode(&command(Command::InvertOff)),
+        Ok(Transfer::Command(0))
+    );
+    assert!(!decoder.inverted());
+
+    // The monochrome panels only clear
+    type M = panel::Ls013b7dh03;
+    assert!(<M as Panel>::command(Command::InvertOn).is_none());
+    let mut decoder = Decoder::<M>::new();
+    let clear = [<M as Panel>::command(Command::Clear).unwrap(), 0];
+    decoder.decode(&clear).unwrap();
+    assert!(decoder.visible_pixel(127, 127) == Palette8::White);
+}
+
+#[test]
+fn malformed() {
+    type P = panel::Lpm013m126a;
+    let mut frame_buffer = FrameBuffer::<P>::new();
+    draw(&mut frame_buffer);
+    let mut decoder = Decoder::<P>::new();
+    let line = line_transfer(&frame_buffer, 7);
+
+    assert_eq!(decoder.decode(&[]), Err(DecodeError::Empty));
+    assert_eq!(decoder.decode(&[0x90]), Err(DecodeError::Empty));
+    assert_eq!(
+        decoder.decode(&[0b_0000_0100, 0]),
+        Err(DecodeError::UnknownCommand(0b_0000_0100))
+    );
+    assert_eq!(
+        decoder.decode(&[0b_1100_0000, 0]),
+        Err(DecodeError::UnknownHeader([0b_1100_0000, 0]))
+    );
+    // Line 176 is one past the last line
+    let mut outside = line.clone();
+    outside[..2].copy_from_slice(&<P as Panel>::line_header(176));
+    assert_eq!(
+        decoder.decode(&outside),
+        Err(DecodeError::LineOutOfRange(176))
+    );
+    assert_eq!(
+        decoder.decode(&line[..line.len() - 1]),
+        Err(DecodeError::Length {
+            expected: <P as Panel>::LINE_COMMAND_OCTETS,
+            actual: <P as Panel>::LINE_COMMAND_OCTETS - 1,
+        })
+    );
+    let mut trailer = line.clone();
+    *trailer.last_mut().unwrap() = 0xff;
+    assert_eq!(decoder.decode(&trailer), Err(DecodeError::Trailer));
+    assert_eq!(
+        decoder.decode(&[0b_0010_0000, 0, 0]),
+        Err(DecodeError::Length {
+            expected: 2,
+            actual: 3
+        })
+    );
+    assert_eq!(
+        decoder.decode(&[0b_0010_0000, 1]),
+        Err(DecodeError::Trailer)
+    );
+
+    // Malformed transfers leave the panel untouched
+    assert_eq!(decoder.errors(), 9);
+    assert_eq!(decoder.lines(), 0);
+    assert!(decoder
+        .frame_buffer()
+        .as_bytes()
+        .iter()
+        .all(|&octet| octet == 0));
+    assert_eq!(decoder.decode(&line), Ok(Transfer::Line(7)));
+    assert_eq!(decoder.frame_buffer().line(7), frame_buffer.line(7));
+
+    // Sharp line addresses start at 1, address 0 is not a line
+    type M = panel::Ls013b7dh03;
+    let mut decoder = Decoder::<M>::new();
+    let mut zero = vec![0u8; <M as Panel>::LINE_COMMAND_OCTETS];
+    zero[0] = 0b_1000_0000;
+    assert_eq!(
+        decoder.decode(&zero),
+        Err(DecodeError::UnknownHeader([0b_1000_0000, 0]))
+    );
+}
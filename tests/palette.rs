@@ -0,0 +1,71 @@
+use embedded_graphics::{
+    image::{GetPixel, ImageRaw},
+    pixelcolor::raw::{RawData, RawU4},
+    prelude::Point,
+};
+use microbit_two::lpm013m126a::Palette8;
+
+const COLOURS: [Palette8; 8] = [
+    Palette8::Black,
+    Palette8::Blue,
+    Palette8::Green,
+    Palette8::Cyan,
+    Palette8::Red,
+    Palette8::Pink,
+    Palette8::Yellow,
+    Palette8::White,
+];
+
+#[test]
+fn index_round_trip() {
+    for (index, &colour) in COLOURS.iter().enumerate() {
+        assert_eq!(u8::from(colour), index as u8);
+        assert!(Palette8::from(index as u8) == colour);
+    }
+    // Indices above 7 are black
+    for index in 8..=255u8 {
+        assert!(Palette8::from(index) == Palette8::Black);
+    }
+}
+
+#[test]
+fn raw_round_trip() {
+    for (index, &colour) in COLOURS.iter().enumerate() {
+        let raw = RawU4::from(colour);
+        assert_eq!(raw.into_inner(), index as u8);
+        assert!(Palette8::from(raw) == colour);
+    }
+    for value in 8..16u8 {
+        assert!(Palette8::from(RawU4::new(value)) == Palette8::Black);
+    }
+}
+
+#[test]
+fn nibble_round_trip() {
+    for (index, &colour) in COLOURS.iter().enumerate() {
+        assert_eq!(colour.nibble(), (index as u8) << 1);
+        assert!(Palette8::from_nibble(colour.nibble()) == colour);
+        // Bit 0 is not a colour bit
+        assert!(Palette8::from_nibble(colour.nibble() | 1) == colour);
+    }
+    assert_eq!(
+        Palette8::colors_byte(Palette8::Red, Palette8::Cyan),
+        0b1000_0110
+    );
+}
+
+#[test]
+fn image_raw() {
+    // Two pixels per octet, the first in the high nibble
+    let data = [0x01, 0x23, 0x45, 0x67, 0x76, 0x54, 0x32, 0x10];
+    let image = ImageRaw::<Palette8>::new(&data, 4);
+    for y in 0..4 {
+        for x in 0..4 {
+            let octet = data[(y * 2 + x / 2) as usize];
+            let index = if x % 2 == 0 { octet >> 4 } else { octet & 0x0f };
+            let pixel = image.pixel(Point::new(x, y)).unwrap();
+            assert!(pixel == COLOURS[index as usize]);
+        }
+    }
+    assert!(image.pixel(Point::new(4, 0)).is_none());
+}
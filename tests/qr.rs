@@ -0,0 +1,175 @@
+//! Compares QR codes with the `qrcodegen` reference encoder
+//!
+//! The mask is chosen by a penalty which differs slightly between the
+//! encoders, the reference uses the mask of the code compared with.
+
+use embedded_graphics::{geometry::Dimensions, Drawable};
+use microbit_two::lpm013m126a::{
+    panel,
+    qr::{ErrorCorrection, Mode, MAX_VERSION, QUIET_ZONE},
+    FrameBuffer, Palette8, QrCode, QrView,
+};
+use qrcodegen::{Mask, QrCodeEcc, QrSegment, Version};
+
+const LEVELS: [(ErrorCorrection, QrCodeEcc); 4] = [
+    (ErrorCorrection::Low, QrCodeEcc::Low),
+    (ErrorCorrection::Medium, QrCodeEcc::Medium),
+    (ErrorCorrection::Quartile, QrCodeEcc::Quartile),
+    (ErrorCorrection::High, QrCodeEcc::High),
+];
+
+const MODES: [Mode; 2] = [Mode::Alphanumeric, Mode::Byte];
+
+fn data(mode: Mode, length: usize) -> Vec<u8> {
+    match mode {
+        Mode::Alphanumeric => b"MICROBIT-V2 $%*+./:0123456789"
+            .iter()
+            .cycle()
+            .take(length)
+            .copied()
+            .collect(),
+        Mode::Byte => (0..length).map(|i| (i * 7 + length) as u8).collect(),
+    }
+}
+
+/// Reference code of the data, at most version `max_version`
+fn reference(
+    data: &[u8],
+    mode: Mode,
+    ecc: QrCodeEcc,
+    max_version: u8,
+    mask: u8,
+) -> Option<qrcodegen::QrCode> {
+    let segment = match mode {
+        Mode::Alphanumeric => QrSegment::make_alphanumeric(std::str::from_utf8(data).unwrap()),
+        Mode::Byte => QrSegment::make_bytes(data),
+    };
+    qrcodegen::QrCode::encode_segments_advanced(
+        &[segment],
+        ecc,
+        Version::MIN,
+        Version::new(max_version),
+        Some(Mask::new(mask)),
+        false,
+    )
+    .ok()
+}
+
+fn assert_same(code: &QrCode, reference: &qrcodegen::QrCode) {
+    assert_eq!(code.version(), reference.version().value());
+    let size = code.size() as i32;
+    assert_eq!(size, reference.size());
+    for y in 0..size {
+        for x in 0..size {
+            assert_eq!(code.module(x, y), reference.get_module(x, y), "{} {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn encode() {
+    for &(ecc, reference_ecc) in LEVELS.iter() {
+        for &mode in MODES.iter() {
+            // Lengths growing by an eighth reach every range of versions
+            let mut length = 1;
+            loop {
+                let data = data(mode, length);
+                let code = match QrCode::encode(&data, mode, ecc) {
+                    Ok(code) => code,
+                    Err(_) => break,
+                };
+                let expected = reference(&data, mode, reference_ecc, 40, code.mask()).unwrap();
+                assert_same(&code, &expected);
+                length += 1 + length / 8;
+            }
+        }
+    }
+}
+
+#[test]
+fn capacity() {
+    for &(ecc, reference_ecc) in LEVELS.iter() {
+        for &mode in MODES.iter() {
+            // Longest data of the largest version supported
+            let (mut fits, mut too_long) = (1, 8192);
+            while too_long - fits > 1 {
+                let length = (fits + too_long) / 2;
+                match reference(&data(mode, length), mode, reference_ecc, MAX_VERSION, 0) {
+                    Some(_) => fits = length,
+                    None => too_long = length,
+                }
+            }
+            let longest = data(mode, fits);
+            let code = QrCode::encode(&longest, mode, ecc).ok().unwrap();
+            assert_eq!(code.version(), MAX_VERSION);
+            let expected = reference(&longest, mode, reference_ecc, 40, code.mask()).unwrap();
+            assert_same(&code, &expected);
+            assert!(QrCode::encode(&data(mode, too_long), mode, ecc).is_err());
+        }
+    }
+}
+
+#[test]
+fn text_mode() {
+    let code = QrCode::encode_text("MICROBIT-0123", ErrorCorrection::Medium)
+        .ok()
+        .unwrap();
+    let expected = reference(
+        b"MICROBIT-0123",
+        Mode::Alphanumeric,
+        QrCodeEcc::Medium,
+        40,
+        code.mask(),
+    );
+    assert_same(&code, &expected.unwrap());
+    let code = QrCode::encode_text("https://microbit.org", ErrorCorrection::Medium)
+        .ok()
+        .unwrap();
+    let expected = reference(
+        b"https://microbit.org",
+        Mode::Byte,
+        QrCodeEcc::Medium,
+        40,
+        code.mask(),
+    );
+    assert_same(&code, &expected.unwrap());
+    assert!(QrCode::encode(b"lower", Mode::Alphanumeric, ErrorCorrection::Low).is_err());
+}
+
+#[test]
+fn view() {
+    for &length in [1, 40, 300].iter() {
+        let data = data(Mode::Byte, length);
+        let code = QrCode::encode(&data, Mode::Byte, ErrorCorrection::Medium)
+            .ok()
+            .unwrap();
+        let mut frame_buffer = FrameBuffer::<panel::Lpm013m126a>::new();
+        frame_buffer.fill(Palette8::Blue);
+        let view = QrView::fit(&code, frame_buffer.bounding_box());
+        view.draw(&mut frame_buffer).unwrap();
+
+        let modules = code.size() as i32 + 2 * QUIET_ZONE as i32;
+        let scale = view.size().width as i32 / modules;
+        assert!(scale >= 1);
+        assert_eq!(view.size().width as i32, modules * scale);
+        let origin = view.bounding_box().top_left;
+        for y in 0..modules * scale {
+            for x in 0..modules * scale {
+                let module =
+                    code.module(x / scale - QUIET_ZONE as i32, y / scale - QUIET_ZONE as i32);
+                let colour = if module {
+                    Palette8::Black
+                } else {
+                    Palette8::White
+                };
+                let (x, y) = ((origin.x + x) as u16, (origin.y + y) as u16);
+                assert!(frame_buffer.get_pixel(x, y) == colour, "{} {}", x, y);
+            }
+        }
+        if origin.x > 0 {
+            // Nothing is drawn outside the view
+            let (x, y) = (origin.x as u16 - 1, origin.y as u16);
+            assert!(frame_buffer.get_pixel(x, y) == Palette8::Blue);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Compares a rendered screen against the golden image in `tests/golden`,
+//! set `UPDATE_GOLDEN` to write the golden image instead.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+};
+
+use embedded_graphics::{
+    geometry::{Point, Size},
+    pixelcolor::Rgb888,
+    primitives::{Primitive, PrimitiveStyle, Rectangle},
+    Drawable,
+};
+use microbit_two::lpm013m126a::{
+    panel, simulator, Dither, Dithering, FrameBuffer, Orientation, Palette8, Rotation,
+};
+
+const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/screen.ppm");
+
+/// Colour bars, a dithered ramp and a rotated marker, without fonts or curves
+/// which depend on the rasterisation of `embedded-graphics`
+fn screen() -> FrameBuffer<panel::Lpm013m126a> {
+    let mut frame_buffer = FrameBuffer::new();
+    frame_buffer.fill(Palette8::White);
+    for index in 0..8u16 {
+        frame_buffer.fill_rect(index * 22, 0, 22, 80, Palette8::from(index as u8));
+    }
+    for step in 0..=16u8 {
+        let level = (step as u16 * 255 / 16) as u8;
+        let colour = Rgb888::new(level, level, 255 - level);
+        let _ = Rectangle::new(Point::new(3 + step as i32 * 10, 88), Size::new(10, 40))
+            .into_styled(PrimitiveStyle::with_fill(colour))
+            .draw(&mut Dither::new(&mut frame_buffer, Dithering::Bayer));
+    }
+    frame_buffer.set_orientation(Orientation::new(Rotation::Rotate90));
+    frame_buffer.fill_rect(0, 0, 40, 20, Palette8::Red);
+    frame_buffer.set_pixel(50, 10, Palette8::Black);
+    frame_buffer
+}
+
+#[test]
+fn golden_image() {
+    let frame_buffer = screen();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        let file = BufWriter::new(File::create(GOLDEN).unwrap());
+        simulator::write_ppm(&frame_buffer, file).unwrap();
+    }
+    let golden = BufReader::new(File::open(GOLDEN).unwrap());
+    assert_eq!(simulator::compare_ppm(&frame_buffer, golden).unwrap(), 0);
+}
+
+#[test]
+fn golden_image_differs() {
+    let mut frame_buffer = screen();
+    frame_buffer.set_pixel(100, 150, Palette8::Green);
+    frame_buffer.set_pixel(101, 150, Palette8::Green);
+    let golden = BufReader::new(File::open(GOLDEN).unwrap());
+    assert_eq!(simulator::compare_ppm(&frame_buffer, golden).unwrap(), 2);
+
+    let small = FrameBuffer::<panel::Ls013b7dh03>::new();
+    let golden = BufReader::new(File::open(GOLDEN).unwrap());
+    assert_eq!(simulator::compare_ppm(&small, golden).unwrap(), 128 * 128);
+}